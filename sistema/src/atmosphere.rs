@@ -0,0 +1,231 @@
+// atmosphere.rs - Capas de atmósfera de los cuerpos (backend gpu)
+//
+// Cada cuerpo con `[body.atmosphere]` dibuja, después de los cuerpos opacos, la
+// esfera de la escena agrandada hasta el borde de su atmósfera con
+// atmosphere.wgsl. La cámara y la luz se pasan en el espacio del cuerpo (radio
+// 1), así el shader no depende de la escala ni del giro del planeta. El
+// resultado se mezcla como color premultiplicado: suma la luz dispersada y
+// oscurece lo que queda detrás según la transmitancia.
+
+use crate::renderer::wgsl_with_prelude;
+use crate::scene::{Atmosphere, Body};
+use crate::simulation::Simulation;
+use nalgebra_glm::{self as glm, Vec3, Vec4};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct AtmosphereUniforms {
+    model: [[f32; 4]; 4],
+    camera: [f32; 4],   // xyz: cámara en espacio del cuerpo, w: radio exterior
+    light: [f32; 4],    // xyz: dirección hacia el sol, w: irradiancia
+    rayleigh: [f32; 4], // rgb: coeficientes, a: altura de escala
+    mie: [f32; 4],      // x: coeficiente, y: altura de escala, z: g, w: intensidad
+}
+
+pub struct Atmospheres {
+    // Caras delanteras de la capa, para la cámara afuera
+    outside: wgpu::RenderPipeline,
+    // Caras traseras: con la cámara dentro de la capa no se ve ninguna delantera
+    inside: wgpu::RenderPipeline,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    stride: usize,
+    // Índice del cuerpo y parámetros de cada atmósfera, en orden de dibujo
+    bodies: Vec<(usize, Atmosphere)>,
+    // Si la cámara está dentro de cada capa en este cuadro
+    camera_inside: Vec<bool>,
+    bytes: Vec<u8>,
+}
+
+impl Atmospheres {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        uniform_layout: &wgpu::BindGroupLayout,
+        vertex_layout: wgpu::VertexBufferLayout,
+        scene_bodies: &[Body],
+    ) -> Self {
+        let bodies: Vec<(usize, Atmosphere)> = scene_bodies
+            .iter()
+            .enumerate()
+            .filter_map(|(i, body)| body.atmosphere.map(|atmosphere| (i, atmosphere)))
+            .collect();
+
+        let size = std::mem::size_of::<AtmosphereUniforms>();
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let stride = size.div_ceil(alignment) * alignment;
+
+        // Un buffer vacío no es válido; sin atmósferas no se dibuja nada
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Atmosphere Uniform Buffer"),
+            size: (bodies.len().max(1) * stride) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("atmosphere_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(size as u64),
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("atmosphere_bind_group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size as u64),
+                }),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Atmosphere Shader"),
+            source: wgpu::ShaderSource::Wgsl(wgsl_with_prelude(include_str!("atmosphere.wgsl")).into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Atmosphere Pipeline Layout"),
+            bind_group_layouts: &[uniform_layout, &layout],
+            push_constant_ranges: &[],
+        });
+
+        // Una sola cara de la capa, detrás de lo que ya esté más cerca; no escribe
+        // profundidad para que otras atmósferas se vean a través
+        let create_pipeline = |label, cull_mode| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: std::slice::from_ref(&vertex_layout),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        Some(wgpu::ColorTargetState {
+                            format: color_format,
+                            blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        }),
+                        Some(wgpu::ColorTargetState {
+                            format: color_format,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::empty(),
+                        }),
+                    ],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(cull_mode),
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth_format,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let outside = create_pipeline("Atmosphere Pipeline", wgpu::Face::Back);
+        let inside = create_pipeline("Atmosphere Inside Pipeline", wgpu::Face::Front);
+
+        let bytes = vec![0; bodies.len() * stride];
+        Atmospheres {
+            outside,
+            inside,
+            buffer,
+            bind_group,
+            stride,
+            camera_inside: vec![false; bodies.len()],
+            bodies,
+            bytes,
+        }
+    }
+
+    // Sube la posición de la cámara y de la luz de este cuadro, vistas desde cada cuerpo
+    pub fn update(&mut self, queue: &wgpu::Queue, simulation: &Simulation) {
+        if self.bodies.is_empty() {
+            return;
+        }
+        let light = simulation.light();
+        let eye = simulation.camera.eye;
+
+        for (slot, (body, atmosphere)) in self.bodies.iter().enumerate() {
+            let model = simulation.world[simulation.body_nodes[*body].mesh];
+            let inverse = glm::inverse(&model);
+            let center = Vec3::new(model[(0, 3)], model[(1, 3)], model[(2, 3)]);
+            let (direction, irradiance) = light.at(&center);
+
+            let camera = inverse * Vec4::new(eye.x, eye.y, eye.z, 1.0);
+            let direction = (inverse * Vec4::new(direction.x, direction.y, direction.z, 0.0))
+                .xyz()
+                .normalize();
+            let [r, g, b] = atmosphere.rayleigh;
+
+            let uniforms = AtmosphereUniforms {
+                model: model.into(),
+                camera: [camera.x, camera.y, camera.z, 1.0 + atmosphere.height],
+                light: [direction.x, direction.y, direction.z, irradiance],
+                rayleigh: [r, g, b, atmosphere.rayleigh_scale_height],
+                mie: [
+                    atmosphere.mie,
+                    atmosphere.mie_scale_height,
+                    atmosphere.mie_g,
+                    atmosphere.intensity,
+                ],
+            };
+            self.camera_inside[slot] = camera.xyz().norm() < uniforms.camera[3];
+            let offset = slot * self.stride;
+            let bytes = bytemuck::bytes_of(&uniforms);
+            self.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        queue.write_buffer(&self.buffer, 0, &self.bytes);
+    }
+
+    // Cuerpos con atmósfera, para ordenarlos junto con los anillos
+    pub fn bodies(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bodies.iter().map(|(body, _)| *body)
+    }
+
+    // Dibuja la atmósfera de `body`, si tiene. La malla es la esfera unitaria de los
+    // cuerpos; el bind group 0 debe estar asignado.
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        body: usize,
+        vertices: wgpu::BufferSlice<'a>,
+        indices: wgpu::BufferSlice<'a>,
+        index_format: wgpu::IndexFormat,
+        index_count: u32,
+    ) {
+        let Some(slot) = self.bodies.iter().position(|(b, _)| *b == body) else {
+            return;
+        };
+        let offset = (slot * self.stride) as wgpu::DynamicOffset;
+        let pipeline = if self.camera_inside[slot] { &self.inside } else { &self.outside };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, vertices);
+        render_pass.set_index_buffer(indices, index_format);
+        render_pass.set_bind_group(1, &self.bind_group, &[offset]);
+        render_pass.draw_indexed(0..index_count, 0, 0..1);
+    }
+}
@@ -0,0 +1,152 @@
+// atmosphere.wgsl - Dispersión atmosférica de Rayleigh y Mie
+//
+// Cada cuerpo con atmósfera dibuja una esfera algo más grande que él (las caras
+// que miran a la cámara, o las del fondo si la cámara está dentro de la capa) y
+// en cada pixel integra la luz del sol dispersada a lo largo del rayo de vista,
+// con una sola dispersión. Todo se calcula en el espacio del cuerpo, donde la
+// superficie es la esfera de radio 1: atmosphere.rs pasa la cámara y la luz ya
+// transformadas.
+//
+// Sale color premultiplicado: rgb es la luz dispersada y alfa cuánto tapa la
+// atmósfera lo que hay detrás (la superficie del planeta o el fondo).
+
+const PI: f32 = 3.14159265;
+const VIEW_SAMPLES: i32 = 16;
+const LIGHT_SAMPLES: i32 = 8;
+// El Mie de las partículas también absorbe; factor habitual entre extinción y dispersión
+const MIE_EXTINCTION: f32 = 1.1;
+
+// `struct Uniforms` la genera renderer::uniforms_wgsl y se agrega al final del código
+
+struct Atmosphere {
+    model: mat4x4<f32>,
+    camera: vec4<f32>,   // xyz: cámara en espacio del cuerpo, w: radio exterior
+    light: vec4<f32>,    // xyz: dirección hacia el sol, w: irradiancia
+    rayleigh: vec4<f32>, // rgb: coeficientes, a: altura de escala
+    mie: vec4<f32>,      // x: coeficiente, y: altura de escala, z: g, w: intensidad
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var<uniform> atmosphere: Atmosphere;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local_pos: vec3<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    let local_pos = input.position * atmosphere.camera.w;
+    var output: VertexOutput;
+    output.clip_position = uniforms.view_proj * atmosphere.model * vec4<f32>(local_pos, 1.0);
+    output.local_pos = local_pos;
+    return output;
+}
+
+// Distancias de entrada y salida del rayo a una esfera centrada en el origen;
+// x > y si no la toca
+fn intersect_sphere(origin: vec3<f32>, direction: vec3<f32>, radius: f32) -> vec2<f32> {
+    let b = dot(origin, direction);
+    let c = dot(origin, origin) - radius * radius;
+    let discriminant = b * b - c;
+    if (discriminant < 0.0) {
+        return vec2<f32>(1.0, -1.0);
+    }
+    let root = sqrt(discriminant);
+    return vec2<f32>(-b - root, -b + root);
+}
+
+// Densidad relativa de cada componente a la altura del punto (1 en la superficie)
+fn densities(point: vec3<f32>) -> vec2<f32> {
+    let altitude = max(length(point) - 1.0, 0.0);
+    return exp(-altitude / vec2<f32>(atmosphere.rayleigh.a, atmosphere.mie.y));
+}
+
+// Profundidad óptica (Rayleigh, Mie) desde el punto hasta salir hacia el sol.
+// Si el planeta está en el camino el punto queda en sombra y devuelve -1.
+fn light_depth(point: vec3<f32>, light_dir: vec3<f32>) -> vec2<f32> {
+    let planet = intersect_sphere(point, light_dir, 1.0);
+    if (planet.x <= planet.y && planet.y > 0.0 && planet.x > 0.0) {
+        return vec2<f32>(-1.0);
+    }
+    let exit = intersect_sphere(point, light_dir, atmosphere.camera.w).y;
+    let step = exit / f32(LIGHT_SAMPLES);
+    var depth = vec2<f32>(0.0);
+    for (var i = 0; i < LIGHT_SAMPLES; i++) {
+        depth += densities(point + light_dir * (f32(i) + 0.5) * step) * step;
+    }
+    return depth;
+}
+
+fn extinction(depth: vec2<f32>) -> vec3<f32> {
+    return exp(-(atmosphere.rayleigh.rgb * depth.x + vec3<f32>(atmosphere.mie.x * MIE_EXTINCTION * depth.y)));
+}
+
+// La atmósfera no alimenta el bloom: su pipeline no escribe el segundo destino
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) emission: vec4<f32>,
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> FragmentOutput {
+    let origin = atmosphere.camera.xyz;
+    let direction = normalize(input.local_pos - origin);
+    let light_dir = atmosphere.light.xyz;
+
+    // Tramo del rayo dentro de la capa: desde la cámara si está adentro, y
+    // hasta la superficie si la toca
+    let shell = intersect_sphere(origin, direction, atmosphere.camera.w);
+    let start = max(shell.x, 0.0);
+    var end = shell.y;
+    let planet = intersect_sphere(origin, direction, 1.0);
+    if (planet.x <= planet.y && planet.x > 0.0) {
+        end = min(end, planet.x);
+    }
+    if (end <= start) {
+        discard;
+    }
+
+    let step = (end - start) / f32(VIEW_SAMPLES);
+    var view_depth = vec2<f32>(0.0);
+    var rayleigh_sum = vec3<f32>(0.0);
+    var mie_sum = vec3<f32>(0.0);
+    for (var i = 0; i < VIEW_SAMPLES; i++) {
+        let point = origin + direction * (start + (f32(i) + 0.5) * step);
+        let density = densities(point) * step;
+        view_depth += density;
+
+        let to_light = light_depth(point, light_dir);
+        if (to_light.x < 0.0) {
+            continue;
+        }
+        let transmittance = extinction(view_depth + to_light);
+        rayleigh_sum += transmittance * density.x;
+        mie_sum += transmittance * density.y;
+    }
+
+    let mu = dot(direction, light_dir);
+    let rayleigh_phase = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+    let g = atmosphere.mie.z;
+    let mie_phase = 3.0 / (8.0 * PI) * ((1.0 - g * g) * (1.0 + mu * mu))
+        / ((2.0 + g * g) * pow(1.0 + g * g - 2.0 * g * mu, 1.5));
+
+    let scattered = rayleigh_sum * atmosphere.rayleigh.rgb * rayleigh_phase
+        + mie_sum * atmosphere.mie.x * mie_phase;
+    let color = scattered * atmosphere.light.w * atmosphere.mie.w;
+
+    let view_transmittance = extinction(view_depth);
+    let coverage = 1.0 - dot(view_transmittance, vec3<f32>(1.0 / 3.0));
+    var output: FragmentOutput;
+    output.color = vec4<f32>(color, coverage);
+    output.emission = vec4<f32>(0.0);
+    return output;
+}
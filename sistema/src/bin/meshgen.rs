@@ -0,0 +1,418 @@
+// meshgen.rs - Genera mallas y las exporta a OBJ, PLY binario o glTF
+//
+// Usa los mismos generadores que el visor (src/meshgen.rs) y, para el planeta
+// con relieve, la misma altura de terreno que colorea rocky_planet_shader
+// (src/noise.rs), así los assets para otras herramientas coinciden con lo que
+// se ve en pantalla. El formato sale de la extensión del archivo de salida.
+
+use sistema::meshgen::{self, MeshData};
+use sistema::noise;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Uso: meshgen <malla> [opciones] [--out <archivo.obj|.ply|.gltf|.glb>]
+  uv-sphere    [--radius <r>] [--subdivisions <n>]    esfera UV, n paralelos y meridianos (30)
+  icosphere    [--radius <r>] [--subdivisions <n>]    icosaedro con n² triángulos por cara (16)
+  cube-sphere  [--radius <r>] [--subdivisions <n>]    cubo inflado, n x n por cara (16)
+  ring         [--inner <r>] [--outer <r>] [--segments <n>]    corona plana de dos caras (1.5, 2.5, 128)
+  torus        [--major <r>] [--minor <r>] [--segments <n>] [--sides <n>]    (1, 0.25, 64, 24)
+  terrain      [--radius <r>] [--subdivisions <n>] [--amplitude <a>]
+               icosfera con el relieve del planeta rocoso; a es la altura máxima en radios (64, 0.05)
+Sin --out se escribe assets/<malla>.obj; la carpeta se crea si falta.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Obj,
+    Ply,
+    Gltf,
+    Glb,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Result<Format, String> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match extension.to_ascii_lowercase().as_str() {
+            "obj" => Ok(Format::Obj),
+            "ply" => Ok(Format::Ply),
+            "gltf" => Ok(Format::Gltf),
+            "glb" => Ok(Format::Glb),
+            _ => Err(format!("formato desconocido '{}': se espera .obj, .ply, .gltf o .glb", path.display())),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    kind: String,
+    // Pares --nombre valor en el orden en que llegaron
+    values: Vec<(String, String)>,
+    out: PathBuf,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let kind = args.next().ok_or("falta el tipo de malla")?;
+        let allowed: &[&str] = match kind.as_str() {
+            "uv-sphere" | "icosphere" | "cube-sphere" => &["radius", "subdivisions"],
+            "ring" => &["inner", "outer", "segments"],
+            "torus" => &["major", "minor", "segments", "sides"],
+            "terrain" => &["radius", "subdivisions", "amplitude"],
+            _ => return Err(format!("tipo de malla desconocido '{}'", kind)),
+        };
+
+        let mut values = Vec::new();
+        let mut out = None;
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("argumento desconocido '{}'", arg))?;
+            let value = args.next().ok_or_else(|| format!("{} requiere un valor", arg))?;
+            if name == "out" {
+                out = Some(PathBuf::from(value));
+            } else if allowed.contains(&name) {
+                values.push((name.to_string(), value));
+            } else {
+                return Err(format!("{} no acepta {}", kind, arg));
+            }
+        }
+
+        let out = out.unwrap_or_else(|| PathBuf::from(format!("assets/{}.obj", kind)));
+        Format::from_path(&out)?;
+        Ok(Options { kind, values, out })
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    // Longitudes: finitas y positivas
+    fn length(&self, name: &str, default: f32) -> Result<f32, String> {
+        match self.value(name) {
+            None => Ok(default),
+            Some(value) => match value.parse::<f32>() {
+                Ok(length) if length.is_finite() && length > 0.0 => Ok(length),
+                _ => Err(format!("--{} espera un número positivo, no '{}'", name, value)),
+            },
+        }
+    }
+
+    fn count(&self, name: &str, default: u32, min: u32) -> Result<u32, String> {
+        match self.value(name) {
+            None => Ok(default),
+            Some(value) => match value.parse::<u32>() {
+                Ok(count) if count >= min => Ok(count),
+                _ => Err(format!("--{} espera un entero mayor o igual a {}, no '{}'", name, min, value)),
+            },
+        }
+    }
+
+    fn mesh(&self) -> Result<MeshData, String> {
+        let mesh = match self.kind.as_str() {
+            "uv-sphere" => meshgen::uv_sphere(self.count("subdivisions", 30, 3)?).scaled(self.length("radius", 1.0)?),
+            "icosphere" => meshgen::icosphere(self.count("subdivisions", 16, 1)?).scaled(self.length("radius", 1.0)?),
+            "cube-sphere" => {
+                meshgen::cube_sphere(self.count("subdivisions", 16, 1)?).scaled(self.length("radius", 1.0)?)
+            }
+            "ring" => {
+                let (inner, outer) = (self.length("inner", 1.5)?, self.length("outer", 2.5)?);
+                if inner >= outer {
+                    return Err(format!("--inner ({}) debe ser menor que --outer ({})", inner, outer));
+                }
+                meshgen::ring(inner, outer, self.count("segments", 128, 3)?)
+            }
+            "torus" => {
+                let (major, minor) = (self.length("major", 1.0)?, self.length("minor", 0.25)?);
+                if minor >= major {
+                    return Err(format!("--minor ({}) debe ser menor que --major ({})", minor, major));
+                }
+                meshgen::torus(major, minor, self.count("segments", 64, 3)?, self.count("sides", 24, 3)?)
+            }
+            "terrain" => {
+                let amplitude = self.length("amplitude", 0.05)?;
+                let sphere = meshgen::icosphere(self.count("subdivisions", 64, 1)?);
+                meshgen::displaced(sphere, |direction| amplitude * terrain(direction))
+                    .scaled(self.length("radius", 1.0)?)
+            }
+            _ => unreachable!("validado en parse"),
+        };
+        Ok(mesh)
+    }
+}
+
+// Relieve en [0, 1] en una dirección de la esfera unitaria: 0 en el océano, 1 en
+// las cumbres más altas. El shader evalúa la altura en la posición local por 5.
+fn terrain(direction: &nalgebra_glm::Vec3) -> f32 {
+    let height = noise::terrain_height(direction * 5.0);
+    ((height - noise::SEA_LEVEL) / (1.0 - noise::SEA_LEVEL)).clamp(0.0, 1.0)
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mesh = match options.mesh() {
+        Ok(mesh) => mesh,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = export(&mesh, &options.kind, &options.out) {
+        eprintln!("Error al escribir {}: {}", options.out.display(), e);
+        std::process::exit(1);
+    }
+    println!(
+        "Malla escrita en {}: {} vértices, {} triángulos",
+        options.out.display(),
+        mesh.vertex_count(),
+        mesh.indices.len() / 3
+    );
+}
+
+fn export(mesh: &MeshData, name: &str, path: &Path) -> io::Result<()> {
+    let format = Format::from_path(path).map_err(io::Error::other)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Obj => write_obj(&mut file, mesh, name)?,
+        Format::Ply => write_ply(&mut file, mesh, name)?,
+        Format::Gltf => write_gltf(&mut file, mesh, name)?,
+        Format::Glb => write_glb(&mut file, mesh, name)?,
+    }
+    file.flush()
+}
+
+// OBJ con posición, UV y normal por vértice. OBJ tiene v = 0 abajo de la imagen,
+// al revés que las UV de meshgen.
+fn write_obj(out: &mut impl Write, mesh: &MeshData, name: &str) -> io::Result<()> {
+    writeln!(out, "# meshgen {}: {} vértices, {} triángulos", name, mesh.vertex_count(), mesh.indices.len() / 3)?;
+    writeln!(out, "o {}", name)?;
+    for p in &mesh.positions {
+        writeln!(out, "v {:.6} {:.6} {:.6}", p.x, p.y, p.z)?;
+    }
+    for uv in &mesh.uvs {
+        writeln!(out, "vt {:.6} {:.6}", uv.x, 1.0 - uv.y)?;
+    }
+    for n in &mesh.normals {
+        writeln!(out, "vn {:.6} {:.6} {:.6}", n.x, n.y, n.z)?;
+    }
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| triangle[k] + 1);
+        writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    Ok(())
+}
+
+// PLY binario little endian con posición, normal y UV (s, t con t hacia arriba
+// como en OBJ). PLY no tiene una propiedad estándar para tangentes.
+fn write_ply(out: &mut impl Write, mesh: &MeshData, name: &str) -> io::Result<()> {
+    write!(
+        out,
+        "ply\nformat binary_little_endian 1.0\ncomment meshgen {}\nelement vertex {}\n\
+         property float x\nproperty float y\nproperty float z\n\
+         property float nx\nproperty float ny\nproperty float nz\n\
+         property float s\nproperty float t\n\
+         element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+        name,
+        mesh.vertex_count(),
+        mesh.indices.len() / 3
+    )?;
+    for i in 0..mesh.vertex_count() {
+        let (p, n, uv) = (mesh.positions[i], mesh.normals[i], mesh.uvs[i]);
+        for value in [p.x, p.y, p.z, n.x, n.y, n.z, uv.x, 1.0 - uv.y] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    for triangle in mesh.indices.chunks_exact(3) {
+        out.write_all(&[3])?;
+        for index in triangle {
+            out.write_all(&index.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+// Buffer binario de glTF y el JSON que lo describe; `uri` es None en .glb, donde
+// el buffer va en el segundo bloque del mismo archivo. glTF usa las mismas
+// convenciones que meshgen: v = 0 arriba, triángulos antihorarios y tangentes
+// con el signo de la bitangente en w.
+fn gltf(mesh: &MeshData, name: &str, embed: bool) -> (String, Vec<u8>) {
+    let mut buffer = Vec::new();
+    let mut views = Vec::new();
+    let mut push_view = |buffer: &mut Vec<u8>, bytes: Vec<u8>, target: u32| {
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+        views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            buffer.len(),
+            bytes.len(),
+            target
+        ));
+        buffer.extend(bytes);
+    };
+    let floats = |values: &mut dyn Iterator<Item = f32>| values.flat_map(f32::to_le_bytes).collect::<Vec<u8>>();
+
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    push_view(&mut buffer, floats(&mut mesh.positions.iter().flat_map(|p| [p.x, p.y, p.z])), ARRAY_BUFFER);
+    push_view(&mut buffer, floats(&mut mesh.normals.iter().flat_map(|n| [n.x, n.y, n.z])), ARRAY_BUFFER);
+    push_view(&mut buffer, floats(&mut mesh.tangents.iter().flat_map(|t| [t.x, t.y, t.z, t.w])), ARRAY_BUFFER);
+    push_view(&mut buffer, floats(&mut mesh.uvs.iter().flat_map(|uv| [uv.x, uv.y])), ARRAY_BUFFER);
+
+    // Índices de 16 bits mientras alcancen, como el renderer
+    let (index_type, index_bytes) = if mesh.vertex_count() <= u16::MAX as usize + 1 {
+        (5123, mesh.indices.iter().flat_map(|&i| (i as u16).to_le_bytes()).collect())
+    } else {
+        (5125, mesh.indices.iter().flat_map(|&i| i.to_le_bytes()).collect())
+    };
+    push_view(&mut buffer, index_bytes, ELEMENT_ARRAY_BUFFER);
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+
+    let bounds = |pick: fn(f32, f32) -> f32, start: f32| {
+        let b = mesh.positions.iter().fold([start; 3], |b, p| [pick(b[0], p.x), pick(b[1], p.y), pick(b[2], p.z)]);
+        format!("[{},{},{}]", b[0], b[1], b[2])
+    };
+    let count = mesh.vertex_count();
+    let accessors = [
+        format!(
+            r#"{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":{},"max":{}}}"#,
+            count,
+            bounds(f32::min, f32::INFINITY),
+            bounds(f32::max, f32::NEG_INFINITY)
+        ),
+        format!(r#"{{"bufferView":1,"componentType":5126,"count":{},"type":"VEC3"}}"#, count),
+        format!(r#"{{"bufferView":2,"componentType":5126,"count":{},"type":"VEC4"}}"#, count),
+        format!(r#"{{"bufferView":3,"componentType":5126,"count":{},"type":"VEC2"}}"#, count),
+        format!(
+            r#"{{"bufferView":4,"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            index_type,
+            mesh.indices.len()
+        ),
+    ];
+
+    let uri = if embed {
+        format!(r#","uri":"data:application/octet-stream;base64,{}""#, base64(&buffer))
+    } else {
+        String::new()
+    };
+    let json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"meshgen"}},"scene":0,"scenes":[{{"nodes":[0]}}],"#,
+            r#""nodes":[{{"mesh":0,"name":"{name}"}}],"#,
+            r#""meshes":[{{"name":"{name}","primitives":[{{"attributes":"#,
+            r#"{{"POSITION":0,"NORMAL":1,"TANGENT":2,"TEXCOORD_0":3}},"indices":4,"mode":4}}]}}],"#,
+            r#""buffers":[{{"byteLength":{length}{uri}}}],"bufferViews":[{views}],"accessors":[{accessors}]}}"#
+        ),
+        name = name,
+        length = buffer.len(),
+        uri = uri,
+        views = views.join(","),
+        accessors = accessors.join(","),
+    );
+    (json, buffer)
+}
+
+// .gltf: un solo archivo JSON con el buffer embebido en base64
+fn write_gltf(out: &mut impl Write, mesh: &MeshData, name: &str) -> io::Result<()> {
+    let (json, _) = gltf(mesh, name, true);
+    out.write_all(json.as_bytes())
+}
+
+// .glb: cabecera, bloque JSON rellenado con espacios y bloque binario con ceros,
+// ambos alineados a 4 bytes
+fn write_glb(out: &mut impl Write, mesh: &MeshData, name: &str) -> io::Result<()> {
+    let (json, buffer) = gltf(mesh, name, false);
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+    out.write_all(b"glTF")?;
+    out.write_all(&2u32.to_le_bytes())?;
+    out.write_all(&(length as u32).to_le_bytes())?;
+    out.write_all(&(json.len() as u32).to_le_bytes())?;
+    out.write_all(b"JSON")?;
+    out.write_all(&json)?;
+    out.write_all(&(buffer.len() as u32).to_le_bytes())?;
+    out.write_all(b"BIN\0")?;
+    out.write_all(&buffer)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |g, (i, &b)| g | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn options_are_checked_per_mesh() {
+        let options = parse(&["torus", "--minor", "0.3", "--out", "x/toro.glb"]).unwrap();
+        assert_eq!(options.length("minor", 0.25), Ok(0.3));
+        assert_eq!(options.out, PathBuf::from("x/toro.glb"));
+        assert_eq!(parse(&["icosphere"]).unwrap().out, PathBuf::from("assets/icosphere.obj"));
+
+        assert!(parse(&["ring", "--sides", "3"]).is_err());
+        assert!(parse(&["cone"]).is_err());
+        assert!(parse(&["icosphere", "--out", "malla.stl"]).is_err());
+        assert!(parse(&["icosphere", "--radius", "-1"]).unwrap().mesh().is_err());
+        assert!(parse(&["ring", "--inner", "3"]).unwrap().mesh().is_err());
+    }
+
+    #[test]
+    fn binary_formats_match_their_headers() {
+        let mesh = meshgen::icosphere(2);
+        let (vertices, triangles) = (mesh.vertex_count(), mesh.indices.len() / 3);
+
+        let mut ply = Vec::new();
+        write_ply(&mut ply, &mesh, "icosphere").unwrap();
+        let header = b"end_header\n";
+        let body = ply.windows(header.len()).position(|w| w == header).unwrap() + header.len();
+        assert_eq!(ply.len() - body, vertices * 8 * 4 + triangles * (1 + 3 * 4));
+
+        let mut glb = Vec::new();
+        write_glb(&mut glb, &mesh, "icosphere").unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize, glb.len());
+        let json_length = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        assert_eq!(json_length % 4, 0);
+        let bin = 20 + json_length;
+        assert_eq!(&glb[bin + 4..bin + 8], b"BIN\0");
+        // Cuatro atributos de 3 + 3 + 4 + 2 floats e índices de 16 bits, alineados a 4
+        let expected = vertices * 12 * 4 + (triangles * 3 * 2).next_multiple_of(4);
+        assert_eq!(u32::from_le_bytes(glb[bin..bin + 4].try_into().unwrap()) as usize, expected);
+    }
+
+    #[test]
+    fn base64_pads_the_last_group() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
@@ -1,4 +1,4 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{self as glm, Mat4, Vec3};
 
 pub struct Camera {
     pub eye: Vec3,
@@ -10,4 +10,45 @@ impl Camera {
     pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
         Camera { eye, center, up }
     }
+
+    // Coloca el ojo sobre una esfera alrededor de `center` (ángulos en radianes)
+    pub fn orbit(&mut self, yaw: f32, pitch: f32, distance: f32) {
+        let offset = Vec3::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        );
+        self.eye = self.center + offset * distance;
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        glm::look_at(&self.eye, &self.center, &self.up)
+    }
+}
+
+pub struct Projection {
+    pub aspect: f32,
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Projection {
+    pub fn new(width: u32, height: u32, fov_y: f32) -> Self {
+        Projection {
+            aspect: width as f32 / height as f32,
+            fov_y,
+            near: 0.01,
+            far: 100.0,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.aspect = width as f32 / height as f32;
+    }
+
+    // Profundidad en [0, 1] como espera wgpu
+    pub fn matrix(&self) -> Mat4 {
+        glm::perspective_rh_zo(self.aspect, self.fov_y, self.near, self.far)
+    }
 }
@@ -0,0 +1,324 @@
+// camera_controller.rs - Control de cámara en modo órbita y vuelo libre
+//
+// No depende de winit: main.rs traduce teclas y eventos del mouse a `Action`s y
+// llamadas a `mouse_motion`/`scroll`, y cada cuadro llama a `update` con el dt.
+
+use crate::camera::Camera;
+use crate::scene::CameraSettings;
+use nalgebra_glm::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Orbit,
+    FreeFly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+    YawLeft,
+    YawRight,
+    PitchUp,
+    PitchDown,
+}
+
+const ACTION_COUNT: usize = 10;
+
+// Radianes por segundo al mantener presionada una flecha
+const KEY_ROTATE_SPEED: f32 = 1.5;
+// Radianes por pixel arrastrado
+const MOUSE_SENSITIVITY: f32 = 0.005;
+// Fracción de la distancia por cada paso de la rueda
+const ZOOM_STEP: f32 = 0.1;
+// Unidades por segundo en vuelo libre
+const FLY_SPEED: f32 = 1.0;
+// Qué tan rápido el estado actual alcanza al objetivo (1/s)
+const SMOOTHING: f32 = 12.0;
+const MAX_PITCH: f32 = 1.5;
+const MIN_DISTANCE: f32 = 0.05;
+const MAX_DISTANCE: f32 = 50.0;
+
+// Aproximación exponencial independiente del framerate
+fn smooth(current: f32, goal: f32, dt: f32) -> f32 {
+    current + (goal - current) * (1.0 - (-SMOOTHING * dt).exp())
+}
+
+fn smooth_vec(current: Vec3, goal: Vec3, dt: f32) -> Vec3 {
+    current + (goal - current) * (1.0 - (-SMOOTHING * dt).exp())
+}
+
+pub struct CameraController {
+    mode: Mode,
+    held: [bool; ACTION_COUNT],
+    dragging: bool,
+
+    // Órbita alrededor de `target`
+    target: Vec3,
+    min_distance: f32,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    goal_yaw: f32,
+    goal_pitch: f32,
+    goal_distance: f32,
+
+    // Vuelo libre
+    position: Vec3,
+    goal_position: Vec3,
+    fly_yaw: f32,
+    fly_pitch: f32,
+}
+
+impl CameraController {
+    pub fn new(settings: &CameraSettings) -> Self {
+        CameraController {
+            mode: Mode::Orbit,
+            held: [false; ACTION_COUNT],
+            dragging: false,
+            target: Vec3::zeros(),
+            min_distance: MIN_DISTANCE,
+            yaw: settings.yaw,
+            pitch: settings.pitch,
+            distance: settings.distance,
+            goal_yaw: settings.yaw,
+            goal_pitch: settings.pitch,
+            goal_distance: settings.distance,
+            position: Vec3::zeros(),
+            goal_position: Vec3::zeros(),
+            fly_yaw: 0.0,
+            fly_pitch: 0.0,
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn set_action(&mut self, action: Action, pressed: bool) {
+        self.held[action as usize] = pressed;
+    }
+
+    pub fn set_dragging(&mut self, dragging: bool) {
+        self.dragging = dragging;
+    }
+
+    // Desplazamiento del mouse en pixeles; sólo rota mientras se arrastra
+    pub fn mouse_motion(&mut self, dx: f32, dy: f32) {
+        if !self.dragging {
+            return;
+        }
+        match self.mode {
+            Mode::Orbit => {
+                self.goal_yaw -= dx * MOUSE_SENSITIVITY;
+                self.goal_pitch = (self.goal_pitch + dy * MOUSE_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+            }
+            Mode::FreeFly => {
+                self.fly_yaw -= dx * MOUSE_SENSITIVITY;
+                self.fly_pitch = (self.fly_pitch - dy * MOUSE_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+            }
+        }
+    }
+
+    // Pasos de la rueda: positivo acerca la cámara
+    pub fn scroll(&mut self, steps: f32) {
+        self.goal_distance = (self.goal_distance * (1.0 - ZOOM_STEP).powf(steps))
+            .clamp(self.min_distance, MAX_DISTANCE);
+    }
+
+    // Cuerpo seguido en modo órbita; la cámara no se acerca a menos de 1.5 radios
+    pub fn set_target(&mut self, target: Vec3, radius: f32) {
+        self.target = target;
+        self.min_distance = (radius * 1.5).max(MIN_DISTANCE);
+        self.goal_distance = self.goal_distance.max(self.min_distance);
+    }
+
+    // Cambia de modo partiendo de la vista actual para evitar saltos
+    pub fn toggle_mode(&mut self, camera: &Camera) {
+        match self.mode {
+            Mode::Orbit => {
+                let forward = (camera.center - camera.eye).normalize();
+                self.position = camera.eye;
+                self.goal_position = camera.eye;
+                self.fly_yaw = (-forward.x).atan2(-forward.z);
+                self.fly_pitch = forward.y.clamp(-1.0, 1.0).asin();
+                self.mode = Mode::FreeFly;
+            }
+            Mode::FreeFly => self.mode = Mode::Orbit,
+        }
+    }
+
+    fn axis(&self, positive: Action, negative: Action) -> f32 {
+        self.held[positive as usize] as i32 as f32 - self.held[negative as usize] as i32 as f32
+    }
+
+    fn fly_forward(&self) -> Vec3 {
+        Vec3::new(
+            -self.fly_pitch.cos() * self.fly_yaw.sin(),
+            self.fly_pitch.sin(),
+            -self.fly_pitch.cos() * self.fly_yaw.cos(),
+        )
+    }
+
+    pub fn update(&mut self, dt: f32, camera: &mut Camera) {
+        let turn = self.axis(Action::YawRight, Action::YawLeft);
+        let tilt = self.axis(Action::PitchUp, Action::PitchDown);
+
+        match self.mode {
+            Mode::Orbit => {
+                self.goal_yaw += turn * KEY_ROTATE_SPEED * dt;
+                self.goal_pitch = (self.goal_pitch + tilt * KEY_ROTATE_SPEED * dt).clamp(-MAX_PITCH, MAX_PITCH);
+
+                // W/S acercan y alejan también en modo órbita
+                let zoom = self.axis(Action::Forward, Action::Backward);
+                self.goal_distance = (self.goal_distance * (-zoom * dt).exp())
+                    .clamp(self.min_distance, MAX_DISTANCE);
+
+                self.yaw = smooth(self.yaw, self.goal_yaw, dt);
+                self.pitch = smooth(self.pitch, self.goal_pitch, dt);
+                self.distance = smooth(self.distance, self.goal_distance, dt);
+
+                camera.center = self.target;
+                camera.orbit(self.yaw, self.pitch, self.distance);
+            }
+            Mode::FreeFly => {
+                self.fly_yaw -= turn * KEY_ROTATE_SPEED * dt;
+                self.fly_pitch = (self.fly_pitch + tilt * KEY_ROTATE_SPEED * dt).clamp(-MAX_PITCH, MAX_PITCH);
+
+                let forward = self.fly_forward();
+                let right = forward.cross(&Vec3::y()).normalize();
+                let movement = forward * self.axis(Action::Forward, Action::Backward)
+                    + right * self.axis(Action::Right, Action::Left)
+                    + Vec3::y() * self.axis(Action::Up, Action::Down);
+                self.goal_position += movement * FLY_SPEED * dt;
+                self.position = smooth_vec(self.position, self.goal_position, dt);
+
+                camera.eye = self.position;
+                camera.center = self.position + forward;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> CameraSettings {
+        CameraSettings {
+            distance: 3.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 45f32.to_radians(),
+        }
+    }
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, 3.0), Vec3::zeros(), Vec3::y())
+    }
+
+    #[test]
+    fn held_key_keeps_rotating() {
+        let mut controller = CameraController::new(&settings());
+        let mut camera = camera();
+
+        controller.set_action(Action::YawRight, true);
+        let mut last_yaw = controller.yaw;
+        for _ in 0..30 {
+            controller.update(1.0 / 60.0, &mut camera);
+            assert!(controller.yaw > last_yaw);
+            last_yaw = controller.yaw;
+        }
+
+        // Al soltar, la cámara se detiene en el objetivo
+        controller.set_action(Action::YawRight, false);
+        for _ in 0..120 {
+            controller.update(1.0 / 60.0, &mut camera);
+        }
+        assert!((controller.yaw - controller.goal_yaw).abs() < 1e-4);
+        assert!((controller.goal_yaw - 30.0 / 60.0 * KEY_ROTATE_SPEED).abs() < 1e-4);
+    }
+
+    #[test]
+    fn smoothing_is_frame_rate_independent() {
+        let mut fast = CameraController::new(&settings());
+        let mut slow = CameraController::new(&settings());
+        let (mut cam_fast, mut cam_slow) = (camera(), camera());
+
+        fast.set_dragging(true);
+        slow.set_dragging(true);
+        fast.mouse_motion(-200.0, 0.0);
+        slow.mouse_motion(-200.0, 0.0);
+
+        for _ in 0..10 {
+            fast.update(0.01, &mut cam_fast);
+        }
+        slow.update(0.1, &mut cam_slow);
+
+        assert!((fast.yaw - slow.yaw).abs() < 1e-4);
+        assert!((cam_fast.eye - cam_slow.eye).norm() < 1e-4);
+    }
+
+    #[test]
+    fn mouse_motion_requires_drag() {
+        let mut controller = CameraController::new(&settings());
+        controller.mouse_motion(100.0, 50.0);
+        assert_eq!(controller.goal_yaw, 0.0);
+        assert_eq!(controller.goal_pitch, 0.0);
+
+        controller.set_dragging(true);
+        controller.mouse_motion(100.0, 50.0);
+        assert!(controller.goal_yaw < 0.0);
+        assert!(controller.goal_pitch > 0.0);
+    }
+
+    #[test]
+    fn scroll_zoom_is_clamped_to_target_radius() {
+        let mut controller = CameraController::new(&settings());
+        controller.set_target(Vec3::new(1.0, 0.0, 0.0), 0.5);
+        controller.scroll(1000.0);
+        assert!((controller.goal_distance - 0.75).abs() < 1e-6);
+
+        controller.scroll(-1000.0);
+        assert_eq!(controller.goal_distance, MAX_DISTANCE);
+    }
+
+    #[test]
+    fn orbit_looks_at_moving_target() {
+        let mut controller = CameraController::new(&settings());
+        let mut camera = camera();
+
+        let target = Vec3::new(2.0, 1.0, -1.0);
+        controller.set_target(target, 0.1);
+        controller.update(1.0 / 60.0, &mut camera);
+
+        assert_eq!(camera.center, target);
+        assert!(((camera.eye - target).norm() - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn free_fly_moves_along_view_direction() {
+        let mut controller = CameraController::new(&settings());
+        let mut camera = camera();
+        controller.toggle_mode(&camera);
+        assert_eq!(controller.mode(), Mode::FreeFly);
+
+        // Misma vista que antes de cambiar de modo
+        controller.update(0.0, &mut camera);
+        assert!((camera.eye - Vec3::new(0.0, 0.0, 3.0)).norm() < 1e-5);
+        assert!((camera.center - Vec3::new(0.0, 0.0, 2.0)).norm() < 1e-5);
+
+        controller.set_action(Action::Forward, true);
+        for _ in 0..600 {
+            controller.update(1.0 / 60.0, &mut camera);
+        }
+        // Diez segundos a FLY_SPEED hacia -z, alcanzados casi por completo por el suavizado
+        assert!((controller.goal_position - Vec3::new(0.0, 0.0, 3.0 - 10.0 * FLY_SPEED)).norm() < 1e-3);
+        assert!(camera.eye.z < 3.0 - 9.0 * FLY_SPEED);
+        assert!(camera.eye.x.abs() < 1e-4 && camera.eye.y.abs() < 1e-4);
+    }
+}
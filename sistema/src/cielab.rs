@@ -0,0 +1,42 @@
+// cielab.rs - Diferencia perceptual de color
+//
+// ΔE en CIELAB entre dos colores sRGB de 8 bits. La usan `--parity` (parity.rs)
+// y las imágenes de referencia de tests/golden.rs, así las dos métricas son la
+// misma.
+
+// sRGB de 8 bits -> CIELAB (iluminante D65); usa los tres primeros componentes,
+// así también sirve un pixel RGBA
+pub fn lab(rgb: &[u8]) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g, b) = (linear(rgb[0]), linear(rgb[1]), linear(rgb[2]));
+
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// ΔE*ab (CIE76): ~2.3 es apenas perceptible
+pub fn delta_e(a: &[u8], b: &[u8]) -> f32 {
+    let (a, b) = (lab(a), lab(b));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_e_spans_the_lightness_range() {
+        assert_eq!(delta_e(&[40, 80, 120], &[40, 80, 120, 255]), 0.0);
+        assert!((delta_e(&[0, 0, 0], &[255, 255, 255]) - 100.0).abs() < 0.1);
+        // El alfa de un pixel RGBA no cuenta
+        assert_eq!(delta_e(&[200, 10, 10, 0], &[200, 10, 10, 255]), 0.0);
+    }
+}
@@ -0,0 +1,123 @@
+// cli.rs - Argumentos de línea de comandos
+
+use crate::post::Tonemap;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Uso: sistema [--scene <archivo.toml>] [--stars <catalogo.csv>] [--backend gpu|cpu] [--watch]
+              [--tonemap aces|reinhard]
+              [--headless [--frames <n>] [--out <directorio>] [--size <ancho>x<alto>] [--time <segundos>]]
+       sistema --parity [--time <segundos>]";
+
+// Quién dibuja: wgpu o el rasterizador en CPU de software.rs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Gpu,
+    Cpu,
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub scene: Option<PathBuf>,
+    pub stars: Option<PathBuf>,
+    pub backend: Backend,
+    // Render fuera de pantalla: cantidad de cuadros, carpeta de salida, resolución
+    // y tiempo de simulación del primer cuadro
+    pub headless: bool,
+    pub frames: Option<u32>,
+    pub out: Option<PathBuf>,
+    pub size: Option<(u32, u32)>,
+    pub time: Option<f32>,
+    // Compara shaders.rs con shader.wgsl en lugar de dibujar la escena
+    pub parity: bool,
+    // Recarga src/shader.wgsl cada vez que cambia, sin recompilar
+    pub watch: bool,
+    // Operador de tonos del backend gpu; None usa el de Tonemap::default
+    pub tonemap: Option<Tonemap>,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scene" => {
+                    let path = args.next().ok_or("--scene requiere un archivo")?;
+                    options.scene = Some(PathBuf::from(path));
+                }
+                "--stars" => {
+                    let path = args.next().ok_or("--stars requiere un archivo")?;
+                    options.stars = Some(PathBuf::from(path));
+                }
+                "--backend" => {
+                    let value = args.next().ok_or("--backend requiere gpu o cpu")?;
+                    options.backend = match value.as_str() {
+                        "gpu" => Backend::Gpu,
+                        "cpu" => Backend::Cpu,
+                        _ => return Err(format!("--backend espera gpu o cpu, no '{}'", value)),
+                    };
+                }
+                "--headless" => options.headless = true,
+                "--parity" => options.parity = true,
+                "--watch" => options.watch = true,
+                "--tonemap" => {
+                    let value = args.next().ok_or("--tonemap requiere aces o reinhard")?;
+                    options.tonemap = Some(value.parse().map_err(|e| format!("--tonemap: {}", e))?);
+                }
+                "--frames" => {
+                    let value = args.next().ok_or("--frames requiere un número")?;
+                    match value.parse::<u32>() {
+                        Ok(frames) if frames > 0 => options.frames = Some(frames),
+                        _ => return Err(format!("--frames espera un entero positivo, no '{}'", value)),
+                    }
+                }
+                "--out" => {
+                    let path = args.next().ok_or("--out requiere un directorio")?;
+                    options.out = Some(PathBuf::from(path));
+                }
+                "--size" => {
+                    let value = args.next().ok_or("--size requiere <ancho>x<alto>")?;
+                    options.size = Some(parse_size(&value).ok_or_else(|| {
+                        format!("--size espera <ancho>x<alto>, p. ej. 640x480, no '{}'", value)
+                    })?);
+                }
+                "--time" => {
+                    let value = args.next().ok_or("--time requiere un número de segundos")?;
+                    match value.parse::<f32>() {
+                        Ok(time) if time.is_finite() => options.time = Some(time),
+                        _ => return Err(format!("--time espera un número de segundos, no '{}'", value)),
+                    }
+                }
+                _ => return Err(format!("argumento desconocido '{}'", arg)),
+            }
+        }
+
+        let headless_only =
+            options.frames.is_some() || options.out.is_some() || options.size.is_some();
+        if !options.headless && headless_only {
+            return Err("--frames, --out y --size sólo se usan con --headless".to_string());
+        }
+        if options.time.is_some() && !options.headless && !options.parity {
+            return Err("--time sólo se usa con --headless o --parity".to_string());
+        }
+        if options.parity && options.headless {
+            return Err("--parity no se combina con --headless".to_string());
+        }
+        if options.watch && (options.headless || options.parity || options.backend == Backend::Cpu) {
+            return Err("--watch sólo se usa con la ventana y el backend gpu".to_string());
+        }
+
+        if options.tonemap.is_some() && (options.parity || options.backend == Backend::Cpu) {
+            return Err("--tonemap sólo se usa con el backend gpu".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
@@ -0,0 +1,209 @@
+// headless.rs - Render fuera de pantalla que guarda cada cuadro como PNG
+//
+// No necesita ventana ni superficie: con wgpu dibuja en una textura y la copia a
+// un buffer legible por la CPU (si no hay GPU usa el adaptador de software); con
+// el backend de CPU lee directamente el framebuffer. Cada cuadro se escribe como
+// `frame_NNNN.png`.
+
+use crate::cli::Backend;
+use crate::post::Tonemap;
+use crate::renderer::Renderer;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::software::SoftwareRenderer;
+use crate::starfield::Star;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+// Paso de tiempo fijo entre cuadros, para que la salida sea reproducible
+const FRAME_RATE: f32 = 60.0;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub struct Options<'a> {
+    pub frames: u32,
+    pub out: &'a Path,
+    pub width: u32,
+    pub height: u32,
+    pub start_time: f32,
+    pub backend: Backend,
+    pub tonemap: Tonemap,
+}
+
+// Dibuja el estado actual de la simulación y devuelve los pixeles en RGBA
+trait Target {
+    fn frame(&mut self, simulation: &Simulation) -> Vec<u8>;
+}
+
+struct GpuTarget {
+    renderer: Renderer,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Target for GpuTarget {
+    fn frame(&mut self, simulation: &Simulation) -> Vec<u8> {
+        self.renderer.render(simulation, &self.view);
+        read_texture(&self.renderer.device, &self.renderer.queue, &self.texture)
+    }
+}
+
+impl Target for SoftwareRenderer {
+    fn frame(&mut self, simulation: &Simulation) -> Vec<u8> {
+        self.render(simulation);
+        self.framebuffer
+            .buffer
+            .iter()
+            .flat_map(|pixel| {
+                let [_, r, g, b] = pixel.to_be_bytes();
+                [r, g, b, 255]
+            })
+            .collect()
+    }
+}
+
+pub fn run(scene: Scene, stars: &[Star], options: &Options) -> Result<(), String> {
+    let mut target: Box<dyn Target> = match options.backend {
+        Backend::Gpu => Box::new(gpu_target(&scene, stars, options)?),
+        Backend::Cpu => Box::new(SoftwareRenderer::new(options.width, options.height, &scene, stars)),
+    };
+    let mut simulation = Simulation::new(scene, options.width, options.height);
+
+    std::fs::create_dir_all(options.out)
+        .map_err(|e| format!("no se pudo crear {}: {}", options.out.display(), e))?;
+
+    for frame in 0..options.frames {
+        let time = options.start_time + frame as f32 / FRAME_RATE;
+        simulation.update(time, 1.0 / FRAME_RATE);
+
+        let pixels = target.frame(&simulation);
+        let path = options.out.join(format!("frame_{:04}.png", frame));
+        write_png(&path, options.width, options.height, &pixels)
+            .map_err(|e| format!("no se pudo escribir {}: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+fn gpu_target(scene: &Scene, stars: &[Star], options: &Options) -> Result<GpuTarget, String> {
+    let (device, queue) = pollster::block_on(request_device())?;
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Headless Target"),
+        size: wgpu::Extent3d {
+            width: options.width,
+            height: options.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut renderer = Renderer::new(device, queue, FORMAT, options.width, options.height, scene, stars);
+    renderer.post.tonemap = options.tonemap;
+
+    Ok(GpuTarget {
+        renderer,
+        texture,
+        view,
+    })
+}
+
+// Primero un adaptador normal; si no hay ninguno, el de software
+pub async fn request_device() -> Result<(wgpu::Device, wgpu::Queue), String> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+
+    let mut adapter = None;
+    for force_fallback_adapter in [false, true] {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
+            .await;
+        if adapter.is_some() {
+            break;
+        }
+    }
+    let adapter = adapter.ok_or("no hay ningún adaptador de wgpu disponible")?;
+
+    let info = adapter.get_info();
+    eprintln!("Adaptador: {} ({:?})", info.name, info.backend);
+
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .map_err(|e| format!("no se pudo crear el dispositivo: {}", e))
+}
+
+// Copia la textura a un buffer mapeable y quita el relleno de cada fila
+// (wgpu exige filas alineadas a 256 bytes)
+fn read_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Vec<u8> {
+    let (width, height) = (texture.width(), texture.height());
+    let row_bytes = width * 4;
+    let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: (padded_row_bytes * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let mapped = slice.get_mapped_range();
+    let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+    for row in mapped.chunks(padded_row_bytes as usize) {
+        pixels.extend_from_slice(&row[..row_bytes as usize]);
+    }
+    drop(mapped);
+    buffer.unmap();
+
+    pixels
+}
+
+fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)
+}
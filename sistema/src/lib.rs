@@ -0,0 +1,10 @@
+// lib.rs - Código compartido por el visor y bin/meshgen.rs
+//
+// Sólo lo que no depende de wgpu ni de la escena: la generación de mallas, el
+// ruido procedural del terreno y la diferencia de color ΔE, que también usan las
+// pruebas de tests/golden.rs. El resto de los módulos vive en el binario
+// `sistema` (main.rs).
+
+pub mod cielab;
+pub mod meshgen;
+pub mod noise;
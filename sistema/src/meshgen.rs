@@ -0,0 +1,545 @@
+// meshgen.rs - Generación de mallas: esferas, anillos y tangentes
+//
+// Tres topologías de esfera unitaria con la misma parametrización de textura:
+// la esfera UV (meridianos y paralelos, con los triángulos apretados en los
+// polos), la icosfera (icosaedro subdividido) y la esfera cúbica (cubo
+// subdividido e inflado). Las dos últimas reparten los vértices casi por igual en
+// toda la superficie, así el ruido procedural y los mapas de normales no se
+// deforman cerca de los polos.
+//
+// Las UV son equirectangulares en las tres: u es la longitud (0 en +x, creciendo
+// hacia +z) y v la colatitud (0 en el polo +y). La costura en u = 0 y los polos
+// se resuelven duplicando vértices, de modo que ningún triángulo interpola u a
+// través de toda la textura. Cada vértice lleva además una tangente con el
+// signo de la bitangente en w, calculada a partir de las UV.
+
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    // xyz: dirección en que crece u sobre la superficie; w: ±1, la bitangente
+    // (dirección en que crece v) es cross(normal, tangente) * w
+    pub tangents: Vec<Vec4>,
+    // Triángulos antihorarios vistos desde afuera
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    // Escala todas las posiciones; normales y tangentes no cambian
+    pub fn scaled(mut self, factor: f32) -> Self {
+        for position in &mut self.positions {
+            *position *= factor;
+        }
+        self
+    }
+}
+
+// Esfera UV: `subdivisions` paralelos y meridianos. La última columna repite la
+// primera con u = 1 y cada polo es una fila de vértices con la u de su columna.
+pub fn uv_sphere(subdivisions: u32) -> MeshData {
+    let n = subdivisions.max(3);
+    let mut mesh = MeshData::default();
+
+    for lat in 0..=n {
+        let theta = lat as f32 * PI / n as f32;
+        for lon in 0..=n {
+            let phi = lon as f32 * 2.0 * PI / n as f32;
+            let position = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            mesh.positions.push(position);
+            mesh.normals.push(position);
+            mesh.uvs.push(Vec2::new(lon as f32 / n as f32, lat as f32 / n as f32));
+        }
+    }
+
+    for lat in 0..n {
+        for lon in 0..n {
+            let first = lat * (n + 1) + lon;
+            let second = first + n + 1;
+            mesh.indices
+                .extend_from_slice(&[first, first + 1, second, second, first + 1, second + 1]);
+        }
+    }
+
+    mesh.tangents = tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh
+}
+
+// Icosfera de frecuencia `subdivisions`: cada cara del icosaedro se divide en
+// subdivisions² triángulos (no hace falta que sea potencia de 2). El icosaedro
+// tiene un vértice en cada polo.
+pub fn icosphere(subdivisions: u32) -> MeshData {
+    let n = subdivisions.max(1);
+
+    // Polos, un anillo de 5 vértices arriba y otro abajo girado media división
+    let ring_y = 1.0 / 5.0_f32.sqrt();
+    let ring_radius = 2.0 / 5.0_f32.sqrt();
+    let mut corners = vec![Vec3::y()];
+    for k in 0..5 {
+        let angle = k as f32 * 2.0 * PI / 5.0;
+        corners.push(Vec3::new(ring_radius * angle.cos(), ring_y, ring_radius * angle.sin()));
+    }
+    for k in 0..5 {
+        let angle = (k as f32 + 0.5) * 2.0 * PI / 5.0;
+        corners.push(Vec3::new(ring_radius * angle.cos(), -ring_y, ring_radius * angle.sin()));
+    }
+    corners.push(-Vec3::y());
+
+    let mut faces = Vec::new();
+    for k in 0..5 {
+        let (upper, next_upper) = (1 + k, 1 + (k + 1) % 5);
+        let (lower, next_lower) = (6 + k, 6 + (k + 1) % 5);
+        faces.push([0, next_upper, upper]);
+        faces.push([upper, next_upper, lower]);
+        faces.push([next_upper, next_lower, lower]);
+        faces.push([lower, next_lower, 11]);
+    }
+
+    // Cada punto de la malla es una combinación entera de las esquinas de su cara
+    // con pesos que suman n; la misma combinación identifica el punto en las
+    // caras vecinas, así los bordes se comparten sin comparar posiciones
+    let mut welded: HashMap<Vec<(usize, u32)>, u32> = HashMap::new();
+    let mut points = Vec::new();
+    let mut point = |weights: [(usize, u32); 3]| -> u32 {
+        let mut key: Vec<(usize, u32)> = weights.into_iter().filter(|&(_, w)| w > 0).collect();
+        key.sort_unstable();
+        *welded.entry(key).or_insert_with(|| {
+            let p: Vec3 = weights.iter().map(|&(c, w)| corners[c] * w as f32).sum();
+            points.push(p.normalize());
+            points.len() as u32 - 1
+        })
+    };
+
+    let mut triangles = Vec::new();
+    for [a, b, c] in faces {
+        // P(i, j) = a + (b - a) i / n + (c - a) j / n
+        let mut at = |i: u32, j: u32| point([(a, n - i - j), (b, i), (c, j)]);
+        for i in 0..n {
+            for j in 0..n - i {
+                triangles.push([at(i, j), at(i + 1, j), at(i, j + 1)]);
+                if i + j + 1 < n {
+                    triangles.push([at(i + 1, j), at(i + 1, j + 1), at(i, j + 1)]);
+                }
+            }
+        }
+    }
+
+    from_sphere_points(&points, &triangles)
+}
+
+// Esfera cúbica: cada cara del cubo es una grilla de subdivisions x subdivisions
+// cuadrados. El cubo se infla con la proyección que conserva mejor las áreas
+// que normalizar sin más. Con subdivisions impar el polo caería sobre la
+// diagonal del cuadrado central, en un triángulo que abarca media vuelta de u, así
+// que se redondea al par siguiente para que cada polo sea un vértice.
+pub fn cube_sphere(subdivisions: u32) -> MeshData {
+    let n = subdivisions.max(1).next_multiple_of(2) as i32;
+    // Coordenadas enteras en [-n, n] del punto del cubo: la clave para compartir bordes
+    let mut welded: HashMap<[i32; 3], u32> = HashMap::new();
+    let mut points = Vec::new();
+    let mut point = |key: [i32; 3]| -> u32 {
+        *welded.entry(key).or_insert_with(|| {
+            let [x, y, z] = key.map(|c| c as f32 / n as f32);
+            let (x2, y2, z2) = (x * x, y * y, z * z);
+            points.push(Vec3::new(
+                x * (1.0 - y2 / 2.0 - z2 / 2.0 + y2 * z2 / 3.0).sqrt(),
+                y * (1.0 - z2 / 2.0 - x2 / 2.0 + z2 * x2 / 3.0).sqrt(),
+                z * (1.0 - x2 / 2.0 - y2 / 2.0 + x2 * y2 / 3.0).sqrt(),
+            ));
+            points.len() as u32 - 1
+        })
+    };
+
+    let mut triangles = Vec::new();
+    for axis in 0..3 {
+        for sign in [1, -1] {
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let mut at = |a: i32, b: i32| {
+                let mut key = [0; 3];
+                key[axis] = sign * n;
+                key[u] = 2 * a - n;
+                key[v] = 2 * b - n;
+                point(key)
+            };
+            for a in 0..n {
+                for b in 0..n {
+                    let quad = [at(a, b), at(a + 1, b), at(a + 1, b + 1), at(a, b + 1)];
+                    triangles.push([quad[0], quad[1], quad[2]]);
+                    triangles.push([quad[0], quad[2], quad[3]]);
+                }
+            }
+        }
+    }
+
+    from_sphere_points(&points, &triangles)
+}
+
+// Corona plana en el plano XZ entre `inner` y `outer`, con las dos caras (normal
+// +y y -y) para que se vea desde ambos lados con descarte de caras traseras.
+// u da la vuelta y v va del borde interior (0) al exterior (1).
+pub fn ring(inner: f32, outer: f32, segments: u32) -> MeshData {
+    let segments = segments.max(3);
+    let mut mesh = MeshData::default();
+
+    for normal_y in [1.0, -1.0] {
+        let base = mesh.positions.len() as u32;
+        for i in 0..=segments {
+            let phi = i as f32 * 2.0 * PI / segments as f32;
+            for (v, radius) in [(0.0, inner), (1.0, outer)] {
+                mesh.positions.push(Vec3::new(radius * phi.cos(), 0.0, radius * phi.sin()));
+                mesh.normals.push(Vec3::new(0.0, normal_y, 0.0));
+                mesh.uvs.push(Vec2::new(i as f32 / segments as f32, v));
+            }
+        }
+
+        for i in 0..segments {
+            let (inner_a, outer_a) = (base + i * 2, base + i * 2 + 1);
+            let (inner_b, outer_b) = (inner_a + 2, outer_a + 2);
+            // Antihorario visto desde el lado al que apunta la normal
+            let quad = if normal_y > 0.0 {
+                [inner_a, inner_b, outer_a, outer_a, inner_b, outer_b]
+            } else {
+                [inner_a, outer_a, inner_b, outer_a, outer_b, inner_b]
+            };
+            mesh.indices.extend_from_slice(&quad);
+        }
+    }
+
+    mesh.tangents = tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh
+}
+
+// Toro alrededor del eje y: `major` es el radio del círculo que recorre el centro
+// del tubo y `minor` el del tubo. u da la vuelta grande (`segments`) y v la del
+// tubo (`sides`), empezando por el borde exterior; la última fila y la última
+// columna repiten la primera con u o v = 1.
+pub fn torus(major: f32, minor: f32, segments: u32, sides: u32) -> MeshData {
+    let (segments, sides) = (segments.max(3), sides.max(3));
+    let mut mesh = MeshData::default();
+
+    for i in 0..=segments {
+        let phi = i as f32 * 2.0 * PI / segments as f32;
+        let outward = Vec3::new(phi.cos(), 0.0, phi.sin());
+        for j in 0..=sides {
+            let theta = j as f32 * 2.0 * PI / sides as f32;
+            let normal = outward * theta.cos() + Vec3::y() * theta.sin();
+            mesh.positions.push(outward * major + normal * minor);
+            mesh.normals.push(normal);
+            mesh.uvs.push(Vec2::new(i as f32 / segments as f32, j as f32 / sides as f32));
+        }
+    }
+
+    for i in 0..segments {
+        for j in 0..sides {
+            let a = i * (sides + 1) + j;
+            let b = a + sides + 1;
+            mesh.indices.extend_from_slice(&[a, a + 1, b, b, a + 1, b + 1]);
+        }
+    }
+
+    mesh.tangents = tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh
+}
+
+// Desplaza cada vértice a lo largo de su normal según `height` de la dirección
+// original y recalcula normales y tangentes. Los vértices duplicados en la
+// costura o los polos están en el mismo punto, así que comparten la normal
+// promediada y la superficie sigue sin saltos.
+pub fn displaced(mut mesh: MeshData, height: impl Fn(&Vec3) -> f32) -> MeshData {
+    for (position, normal) in mesh.positions.iter_mut().zip(&mesh.normals) {
+        *position += normal * height(normal);
+    }
+
+    let key = |p: &Vec3| [p.x, p.y, p.z].map(f32::to_bits);
+    let mut smooth: HashMap<[u32; 3], Vec3> = HashMap::new();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| mesh.positions[triangle[k] as usize]);
+        // Sin normalizar: las caras más grandes pesan más
+        let normal = (b - a).cross(&(c - a));
+        for p in [a, b, c] {
+            *smooth.entry(key(&p)).or_insert(Vec3::zeros()) += normal;
+        }
+    }
+    for (position, normal) in mesh.positions.iter().zip(&mut mesh.normals) {
+        *normal = smooth[&key(position)].try_normalize(1e-12).unwrap_or(*normal);
+    }
+
+    mesh.tangents = tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh
+}
+
+// Longitud en [0, 1): 0 en +x, creciendo hacia +z como en la esfera UV
+fn longitude(p: &Vec3) -> f32 {
+    let u = p.z.atan2(p.x) / (2.0 * PI);
+    if u < 0.0 { u + 1.0 } else { u }
+}
+
+// Malla final de puntos de la esfera unitaria soldados y sus triángulos: da
+// vuelta los que miran hacia adentro y reparte las UV. Un vértice se duplica
+// cuando sus triángulos necesitan otra u: del lado u = 1 de la costura, o en un
+// polo, donde toma la u media del triángulo.
+fn from_sphere_points(points: &[Vec3], triangles: &[[u32; 3]]) -> MeshData {
+    let mut mesh = MeshData::default();
+    let mut vertices: HashMap<(u32, i64), u32> = HashMap::new();
+    let is_pole = |p: &Vec3| p.x * p.x + p.z * p.z < 1e-10;
+
+    for &triangle in triangles {
+        let [a, b, c] = triangle.map(|i| points[i as usize]);
+        let triangle = if (b - a).cross(&(c - a)).dot(&(a + b + c)) < 0.0 {
+            [triangle[0], triangle[2], triangle[1]]
+        } else {
+            triangle
+        };
+
+        let poles = triangle.map(|i| is_pole(&points[i as usize]));
+        let mut u = triangle.map(|i| longitude(&points[i as usize]));
+        let others = || (0..3).filter(|&k| !poles[k]);
+        let min = others().map(|k| u[k]).fold(f32::INFINITY, f32::min);
+        let max = others().map(|k| u[k]).fold(f32::NEG_INFINITY, f32::max);
+        if max - min > 0.5 {
+            for k in others() {
+                if u[k] < 0.5 {
+                    u[k] += 1.0;
+                }
+            }
+        }
+        let count = others().count().max(1) as f32;
+        let mean = others().map(|k| u[k]).sum::<f32>() / count;
+        for k in 0..3 {
+            if poles[k] {
+                u[k] = mean;
+            }
+        }
+
+        for k in 0..3 {
+            let point = triangle[k];
+            let key = (point, (u[k] * 1e6).round() as i64);
+            let index = *vertices.entry(key).or_insert_with(|| {
+                let p = points[point as usize];
+                mesh.positions.push(p);
+                mesh.normals.push(p);
+                mesh.uvs.push(Vec2::new(u[k], p.y.clamp(-1.0, 1.0).acos() / PI));
+                mesh.positions.len() as u32 - 1
+            });
+            mesh.indices.push(index);
+        }
+    }
+
+    mesh.tangents = tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh
+}
+
+// Tangentes por vértice a partir de las UV de sus triángulos (suma de las
+// derivadas de la posición respecto de u y v), ortogonalizadas contra la normal.
+// Sirve para cualquier malla indexada, también las cargadas de OBJ.
+pub fn tangents(positions: &[Vec3], normals: &[Vec3], uvs: &[Vec2], indices: &[u32]) -> Vec<Vec4> {
+    let mut along_u = vec![Vec3::zeros(); positions.len()];
+    let mut along_v = vec![Vec3::zeros(); positions.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| triangle[k] as usize);
+        let (e1, e2) = (positions[b] - positions[a], positions[c] - positions[a]);
+        let (d1, d2) = (uvs[b] - uvs[a], uvs[c] - uvs[a]);
+        let r = d1.x * d2.y - d2.x * d1.y;
+        if r.abs() < 1e-12 {
+            continue;
+        }
+        let du = (e1 * d2.y - e2 * d1.y) / r;
+        let dv = (e2 * d1.x - e1 * d2.x) / r;
+        for i in [a, b, c] {
+            along_u[i] += du;
+            along_v[i] += dv;
+        }
+    }
+
+    normals
+        .iter()
+        .zip(along_u.iter().zip(&along_v))
+        .map(|(normal, (u, v))| {
+            let tangent = (u - normal * normal.dot(u))
+                .try_normalize(1e-12)
+                .unwrap_or_else(|| any_perpendicular(normal));
+            let w = if normal.cross(&tangent).dot(v) < 0.0 { -1.0 } else { 1.0 };
+            Vec4::new(tangent.x, tangent.y, tangent.z, w)
+        })
+        .collect()
+}
+
+// Para vértices sin UV útiles: cualquier dirección sobre el plano tangente
+fn any_perpendicular(normal: &Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    (axis - normal * normal.dot(&axis))
+        .try_normalize(1e-12)
+        .unwrap_or(Vec3::x())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cerrada, orientada y de género 0 una vez soldados los vértices repetidos en
+    // la costura y los polos: cada arista de un triángulo no degenerado se usa una
+    // vez en cada sentido y V - A + C = 2
+    fn assert_closed_sphere(mesh: &MeshData) {
+        assert_eq!(mesh.indices.len() % 3, 0);
+        assert!(mesh.indices.iter().all(|&i| (i as usize) < mesh.vertex_count()));
+
+        let mut welded = HashMap::new();
+        let ids: Vec<usize> = mesh
+            .positions
+            .iter()
+            .map(|p| {
+                let key = [p.x, p.y, p.z].map(|c| (c * 1e5).round() as i32);
+                let next = welded.len();
+                *welded.entry(key).or_insert(next)
+            })
+            .collect();
+
+        let mut edges: HashMap<(usize, usize), i32> = HashMap::new();
+        let mut faces = 0;
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| ids[triangle[k] as usize]);
+            if a == b || b == c || c == a {
+                continue;
+            }
+            faces += 1;
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                // +1 en un sentido y -1 en el otro: una superficie cerrada suma 0
+                let sign = if from < to { 1 } else { -1 };
+                let entry = edges.entry((from.min(to), from.max(to))).or_insert(0);
+                *entry += sign;
+                assert!(entry.abs() <= 1, "arista ({}, {}) usada dos veces en el mismo sentido", from, to);
+            }
+        }
+        assert!(edges.values().all(|&sum| sum == 0), "la malla tiene aristas abiertas");
+        assert_eq!(welded.len() as i64 - edges.len() as i64 + faces, 2);
+    }
+
+    // Todas las caras miran hacia afuera y ningún triángulo cruza la costura
+    fn assert_outward_and_seamless(mesh: &MeshData) {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| triangle[k] as usize);
+            let (pa, pb, pc) = (mesh.positions[a], mesh.positions[b], mesh.positions[c]);
+            // Los triángulos degenerados de los polos de la esfera UV dan ~0
+            assert!((pb - pa).cross(&(pc - pa)).dot(&(pa + pb + pc)) > -1e-6);
+
+            let u = [a, b, c].map(|i| mesh.uvs[i].x);
+            let span = u.iter().cloned().fold(f32::MIN, f32::max) - u.iter().cloned().fold(f32::MAX, f32::min);
+            assert!(span < 0.5, "triángulo con u de {:?}", u);
+        }
+    }
+
+    fn assert_unit_tangents(mesh: &MeshData) {
+        for (normal, tangent) in mesh.normals.iter().zip(&mesh.tangents) {
+            let t = tangent.xyz();
+            assert!((t.norm() - 1.0).abs() < 1e-4);
+            assert!(normal.dot(&t).abs() < 1e-4);
+            assert!(tangent.w == 1.0 || tangent.w == -1.0);
+        }
+    }
+
+    #[test]
+    fn spheres_are_closed_with_seamless_uvs() {
+        for mesh in [uv_sphere(12), icosphere(1), icosphere(5), cube_sphere(1), cube_sphere(6), cube_sphere(7)] {
+            assert_closed_sphere(&mesh);
+            assert_outward_and_seamless(&mesh);
+            assert_unit_tangents(&mesh);
+            assert!(mesh.positions.iter().all(|p| (p.norm() - 1.0).abs() < 1e-5));
+        }
+        assert_eq!(icosphere(5).indices.len() / 3, 20 * 25);
+        assert_eq!(cube_sphere(6).indices.len() / 3, 6 * 2 * 36);
+        assert_eq!(cube_sphere(7).indices.len() / 3, 6 * 2 * 64);
+    }
+
+    #[test]
+    fn high_resolution_spheres_are_closed() {
+        let mesh = uv_sphere(1000);
+        assert_eq!(mesh.vertex_count(), 1001 * 1001);
+        assert_eq!(mesh.indices.len() / 3, 2 * 1000 * 1000);
+        assert_closed_sphere(&mesh);
+        assert_closed_sphere(&icosphere(60));
+        assert_closed_sphere(&cube_sphere(80));
+    }
+
+    #[test]
+    fn tangents_follow_u_around_the_equator() {
+        // En el ecuador u crece hacia +z desde +x, como la rotación alrededor de y
+        let mesh = icosphere(8);
+        for ((p, tangent), uv) in mesh.positions.iter().zip(&mesh.tangents).zip(&mesh.uvs) {
+            if p.y.abs() < 0.2 && uv.x > 0.05 && uv.x < 0.95 {
+                let east = Vec3::new(-p.z, 0.0, p.x).normalize();
+                assert!(tangent.xyz().dot(&east) > 0.9, "{:?} en {:?}", tangent, p);
+                // v crece hacia -y, el mismo sentido que cross(n, t)
+                assert_eq!(tangent.w, 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn icosphere_and_cube_sphere_do_not_pinch_at_the_poles() {
+        // Razón entre la arista más larga y la más corta: la esfera UV crece sin
+        // límite con la resolución, las otras dos se mantienen acotadas
+        let ratio = |mesh: &MeshData| {
+            let lengths: Vec<f32> = mesh
+                .indices
+                .chunks_exact(3)
+                .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+                .map(|(a, b)| (mesh.positions[a as usize] - mesh.positions[b as usize]).norm())
+                .filter(|&length| length > 1e-6)
+                .collect();
+            let max = lengths.iter().cloned().fold(0.0, f32::max);
+            let min = lengths.iter().cloned().fold(f32::MAX, f32::min);
+            max / min
+        };
+        assert!(ratio(&uv_sphere(64)) > 20.0);
+        assert!(ratio(&icosphere(16)) < 2.0);
+        assert!(ratio(&cube_sphere(16)) < 2.5);
+    }
+
+    #[test]
+    fn ring_has_both_faces() {
+        let mesh = ring(1.5, 2.0, 16);
+        assert_eq!(mesh.indices.len() / 3, 2 * 2 * 16);
+        let up = mesh.normals.iter().filter(|n| n.y > 0.0).count();
+        assert_eq!(up * 2, mesh.vertex_count());
+        assert_unit_tangents(&mesh);
+    }
+
+    #[test]
+    fn torus_is_closed_and_displacement_keeps_it() {
+        let mesh = torus(1.0, 0.25, 24, 12);
+        assert_eq!(mesh.indices.len() / 3, 2 * 24 * 12);
+        assert_unit_tangents(&mesh);
+        // El toro tiene género 1: V - A + C = 0
+        let mut edges = std::collections::HashSet::new();
+        let weld = |i: u32| {
+            let p = mesh.positions[i as usize];
+            [p.x, p.y, p.z].map(|c| (c * 1e4).round() as i32)
+        };
+        let vertices: std::collections::HashSet<_> = (0..mesh.vertex_count() as u32).map(weld).collect();
+        for t in mesh.indices.chunks_exact(3) {
+            for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                let (a, b) = (weld(a), weld(b));
+                edges.insert(if a < b { (a, b) } else { (b, a) });
+            }
+        }
+        assert_eq!(vertices.len() as i64 - edges.len() as i64 + (mesh.indices.len() / 3) as i64, 0);
+
+        // Desplazar una esfera con costura no la abre
+        let bumpy = displaced(icosphere(6), |n| 0.1 * (n.x * 7.0).sin());
+        assert_closed_sphere(&bumpy);
+        assert_unit_tangents(&bumpy);
+        for (p, n) in bumpy.positions.iter().zip(&bumpy.normals) {
+            assert!((p.norm() - 1.0).abs() <= 0.1001);
+            assert!(n.dot(&p.normalize()) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// noise.rs - Ruido procedural compartido
+//
+// Las funciones de ruido de los shaders de CPU y la altura del terreno rocoso.
+// shader.wgsl tiene su propia copia en WGSL; bin/meshgen.rs usa la misma altura
+// para desplazar la malla del planeta que colorea el shader.
+
+use nalgebra_glm::Vec3;
+
+// Altura por debajo de la cual rocky_planet_shader pinta océano
+pub const SEA_LEVEL: f32 = 0.35;
+
+// Funciones matemáticas rápidas para patrones procedurales
+#[inline(always)]
+pub fn fast_noise(p: Vec3) -> f32 {
+    ((p.x * 12.9898 + p.y * 78.233 + p.z * 37.719).sin() * 43758.545).fract()
+}
+
+#[inline(always)]
+pub fn voronoi_simple(p: Vec3) -> f32 {
+    let pi = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
+    let pf = Vec3::new(p.x.fract(), p.y.fract(), p.z.fract());
+    
+    let mut min_dist: f32 = 2.0;
+    for i in -1..=1 {
+        for j in -1..=1 {
+            let neighbor = Vec3::new(i as f32, j as f32, 0.0);
+            let point = neighbor + Vec3::new(
+                fast_noise(pi + neighbor),
+                fast_noise(pi + neighbor + Vec3::new(0.1, 0.1, 0.1)),
+                0.0
+            );
+            let diff = point - pf;
+            let dist = diff.x * diff.x + diff.y * diff.y;
+            min_dist = min_dist.min(dist);
+        }
+    }
+    min_dist.sqrt()
+}
+
+// Altura en [0, 1] del terreno rocoso en `pos` (la posición local ya escalada
+// por el shader): continentes Voronoi más cordilleras
+pub fn terrain_height(pos: Vec3) -> f32 {
+    let continents = voronoi_simple(pos * 0.8);
+    let mountains = ((pos.x * 10.0).sin() * (pos.y * 10.0).cos() + (pos.z * 10.0).sin() + 1.0) * 0.5;
+
+    continents * 0.7 + mountains * 0.3
+}
@@ -1,104 +1,596 @@
-use nalgebra_glm::Vec3;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use crate::vertex::Vertex;
+// obj_loader.rs - Mallas Wavefront OBJ con sus materiales MTL
+//
+// Lee posiciones, coordenadas de textura y normales, y caras de cualquier
+// cantidad de vértices, que se triangulan al leerlas. Los índices pueden ser
+// absolutos (desde 1) o relativos (negativos, contando hacia atrás desde el
+// último elemento declarado). Las caras se reparten en grupos según `o`, `g` y
+// `usemtl`, y `load` también lee los `mtllib` que nombre el archivo. Cualquier
+// línea que no se entienda es un error con el archivo y el número de línea.
 
+use nalgebra_glm::{Vec2, Vec3};
+use std::fmt;
+use std::path::Path;
+
+// Un vértice de una cara: índices (desde 0) a posición, textura y normal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Corner {
+    pub position: usize,
+    pub tex_coord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+// Caras seguidas que comparten objeto, grupo y material
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub material: Option<String>,
+    pub triangles: Vec<[Corner; 3]>,
+}
+
+// Los campos de MTL que tienen sentido para el renderer; el resto se ignora
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emissive: [f32; 3],
+    pub shininess: f32,
+    pub opacity: f32,
+    // Ruta tal como aparece en el MTL, relativa a él
+    pub diffuse_map: Option<String>,
+}
+
+impl Material {
+    fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            ambient: [0.0; 3],
+            diffuse: [0.8; 3],
+            specular: [0.0; 3],
+            emissive: [0.0; 3],
+            shininess: 0.0,
+            opacity: 1.0,
+            diffuse_map: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    Invalid {
+        origin: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path, source),
+            ObjError::Invalid {
+                origin,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", origin, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Obj {
-    vertices: Vec<Vec3>,
-    normals: Vec<Vec3>,
-    tex_coords: Vec<Vec3>,
-    faces: Vec<[usize; 9]>,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    // u, v y w (0 si el archivo no la da)
+    pub tex_coords: Vec<Vec3>,
+    pub groups: Vec<Group>,
+    // Archivos de `mtllib`, en el orden en que aparecen
+    pub material_libraries: Vec<String>,
+    // Materiales de esas bibliotecas; sólo los carga `load`
+    pub materials: Vec<Material>,
+    // Línea de cada `usemtl`, para señalarla si el material no existe
+    material_uses: Vec<(String, usize)>,
+}
+
+// Líneas lógicas del archivo: sin comentarios, con las terminadas en `\` unidas a
+// la siguiente. Cada una lleva el número de su primera línea física.
+fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, raw) in source.lines().enumerate() {
+        let text = raw.split('#').next().unwrap_or("");
+        let (number, mut line) = pending.take().unwrap_or((i + 1, String::new()));
+        match text.trim_end().strip_suffix('\\') {
+            Some(continued) => {
+                line.push_str(continued);
+                line.push(' ');
+                pending = Some((number, line));
+            }
+            None => {
+                line.push_str(text);
+                lines.push((number, line));
+            }
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+// Contexto para armar errores de una línea
+struct Line<'a> {
+    origin: &'a str,
+    number: usize,
+}
+
+impl Line<'_> {
+    fn error(&self, message: String) -> ObjError {
+        ObjError::Invalid {
+            origin: self.origin.to_string(),
+            line: self.number,
+            message,
+        }
+    }
+
+    fn float(&self, text: &str) -> Result<f32, ObjError> {
+        text.parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| self.error(format!("número inválido '{}'", text)))
+    }
+
+    // Entre `min` y `max` números después de la palabra clave
+    fn floats(&self, keyword: &str, args: &[&str], min: usize, max: usize) -> Result<Vec<f32>, ObjError> {
+        if args.len() < min || args.len() > max {
+            let expected = if min == max {
+                min.to_string()
+            } else {
+                format!("entre {} y {}", min, max)
+            };
+            return Err(self.error(format!(
+                "'{}' espera {} números, tiene {}",
+                keyword,
+                expected,
+                args.len()
+            )));
+        }
+        args.iter().map(|arg| self.float(arg)).collect()
+    }
+
+    fn color(&self, keyword: &str, args: &[&str]) -> Result<[f32; 3], ObjError> {
+        // `Kd r` vale como gris; `Kd r g b` es lo habitual
+        match self.floats(keyword, args, 1, 3)?.as_slice() {
+            [v] => Ok([*v; 3]),
+            [r, g, b] => Ok([*r, *g, *b]),
+            _ => Err(self.error(format!("'{}' espera 1 o 3 números", keyword))),
+        }
+    }
+
+    // Índice de OBJ (desde 1, o negativo relativo al final) a índice desde 0
+    fn index(&self, text: &str, count: usize, kind: &str) -> Result<usize, ObjError> {
+        let value: i64 = text
+            .parse()
+            .map_err(|_| self.error(format!("índice de {} inválido '{}'", kind, text)))?;
+        let index = match value {
+            0 => None,
+            v if v > 0 => Some(v as usize - 1),
+            v => count.checked_sub(v.unsigned_abs() as usize),
+        };
+        index.filter(|&i| i < count).ok_or_else(|| {
+            self.error(format!(
+                "índice de {} {} fuera de rango (hay {})",
+                kind, value, count
+            ))
+        })
+    }
 }
 
 impl Obj {
-    pub fn load(filename: &str) -> Result<Self, std::io::Error> {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
+    // Lee el OBJ y los MTL que nombra, buscándolos junto a él
+    pub fn load(path: &Path) -> Result<Self, ObjError> {
+        let source = read(path)?;
+        let mut obj = Obj::parse(&source, &path.display().to_string())?;
 
-        let mut vertices = Vec::new();
-        let mut normals = Vec::new();
-        let mut tex_coords = Vec::new();
-        let mut faces = Vec::new();
+        let directory = path.parent().unwrap_or(Path::new(""));
+        for library in &obj.material_libraries {
+            let path = directory.join(library);
+            let source = read(&path)?;
+            obj.materials
+                .extend(parse_mtl(&source, &path.display().to_string())?);
+        }
 
-        for line in reader.lines() {
-            let line = line?;
-            let parts: Vec<&str> = line.split_whitespace().collect();
+        for (name, line) in &obj.material_uses {
+            if obj.material(name).is_none() {
+                return Err(ObjError::Invalid {
+                    origin: path.display().to_string(),
+                    line: *line,
+                    message: format!("material '{}' no definido en ningún mtllib", name),
+                });
+            }
+        }
+        Ok(obj)
+    }
+
+    // Sólo el OBJ: los `mtllib` quedan en `material_libraries` sin leer.
+    // `origin` identifica el archivo en los mensajes de error.
+    pub fn parse(source: &str, origin: &str) -> Result<Self, ObjError> {
+        let mut obj = Obj::default();
+        let mut group = Group {
+            name: String::new(),
+            material: None,
+            triangles: Vec::new(),
+        };
+        let mut polygon = Vec::new();
 
-            if parts.is_empty() {
+        for (number, text) in logical_lines(source) {
+            let line = Line { origin, number };
+            let mut parts = text.split_whitespace();
+            let Some(keyword) = parts.next() else {
                 continue;
-            }
+            };
+            let args: Vec<&str> = parts.collect();
 
-            match parts[0] {
+            match keyword {
                 "v" => {
-                    if parts.len() >= 4 {
-                        let x: f32 = parts[1].parse().unwrap_or(0.0);
-                        let y: f32 = parts[2].parse().unwrap_or(0.0);
-                        let z: f32 = parts[3].parse().unwrap_or(0.0);
-                        vertices.push(Vec3::new(x, y, z));
-                    }
+                    // Puede traer w o un color por vértice; sólo se usa xyz
+                    let v = line.floats(keyword, &args, 3, 7)?;
+                    obj.positions.push(Vec3::new(v[0], v[1], v[2]));
                 }
                 "vn" => {
-                    if parts.len() >= 4 {
-                        let x: f32 = parts[1].parse().unwrap_or(0.0);
-                        let y: f32 = parts[2].parse().unwrap_or(0.0);
-                        let z: f32 = parts[3].parse().unwrap_or(0.0);
-                        normals.push(Vec3::new(x, y, z));
-                    }
+                    let n = line.floats(keyword, &args, 3, 3)?;
+                    obj.normals.push(Vec3::new(n[0], n[1], n[2]));
                 }
                 "vt" => {
-                    if parts.len() >= 3 {
-                        let u: f32 = parts[1].parse().unwrap_or(0.0);
-                        let v: f32 = parts[2].parse().unwrap_or(0.0);
-                        tex_coords.push(Vec3::new(u, v, 0.0));
-                    }
+                    let t = line.floats(keyword, &args, 1, 3)?;
+                    let get = |i: usize| t.get(i).copied().unwrap_or(0.0);
+                    obj.tex_coords.push(Vec3::new(get(0), get(1), get(2)));
                 }
                 "f" => {
-                    if parts.len() >= 4 {
-                        let mut face = [0; 9];
-                        for (i, part) in parts.iter().skip(1).take(3).enumerate() {
-                            let indices: Vec<&str> = part.split('/').collect();
-                            if !indices.is_empty() {
-                                face[i * 3] = indices[0].parse::<usize>().unwrap_or(1) - 1;
-                            }
-                            if indices.len() > 1 && !indices[1].is_empty() {
-                                face[i * 3 + 1] = indices[1].parse::<usize>().unwrap_or(1) - 1;
-                            }
-                            if indices.len() > 2 {
-                                face[i * 3 + 2] = indices[2].parse::<usize>().unwrap_or(1) - 1;
-                            }
-                        }
-                        faces.push(face);
+                    if args.len() < 3 {
+                        return Err(line.error(format!(
+                            "una cara necesita al menos 3 vértices, tiene {}",
+                            args.len()
+                        )));
                     }
+                    polygon.clear();
+                    for arg in &args {
+                        polygon.push(obj.corner(&line, arg)?);
+                    }
+                    let positions: Vec<Vec3> =
+                        polygon.iter().map(|c| obj.positions[c.position]).collect();
+                    group.triangles.extend(
+                        triangulate(&positions)
+                            .into_iter()
+                            .map(|[a, b, c]| [polygon[a], polygon[b], polygon[c]]),
+                    );
+                }
+                "o" | "g" => {
+                    let material = group.material.clone();
+                    obj.start_group(&mut group, args.join(" "), material);
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    if name.is_empty() {
+                        return Err(line.error("'usemtl' sin nombre de material".to_string()));
+                    }
+                    obj.material_uses.push((name.clone(), number));
+                    let group_name = group.name.clone();
+                    obj.start_group(&mut group, group_name, Some(name));
                 }
-                _ => {}
+                "mtllib" => {
+                    if args.is_empty() {
+                        return Err(line.error("'mtllib' sin archivo".to_string()));
+                    }
+                    obj.material_libraries
+                        .extend(args.iter().map(|arg| arg.to_string()));
+                }
+                // Suavizado, líneas, puntos y superficies libres no se dibujan
+                "s" | "l" | "p" | "vp" | "cstype" | "deg" | "curv" | "curv2" | "surf" | "parm"
+                | "trim" | "hole" | "end" | "mg" | "lod" | "bevel" | "c_interp" | "d_interp"
+                | "usemap" | "maplib" | "shadow_obj" | "trace_obj" => {}
+                _ => return Err(line.error(format!("instrucción desconocida '{}'", keyword))),
             }
         }
 
-        Ok(Obj {
-            vertices,
-            normals,
-            tex_coords,
-            faces,
+        obj.start_group(&mut group, String::new(), None);
+        Ok(obj)
+    }
+
+    // Cierra el grupo actual (si tiene caras) y empieza otro
+    fn start_group(&mut self, group: &mut Group, name: String, material: Option<String>) {
+        let next = Group {
+            name,
+            material,
+            triangles: Vec::new(),
+        };
+        let done = std::mem::replace(group, next);
+        if !done.triangles.is_empty() {
+            self.groups.push(done);
+        }
+    }
+
+    // `v`, `v/vt`, `v//vn` o `v/vt/vn`
+    fn corner(&self, line: &Line, text: &str) -> Result<Corner, ObjError> {
+        let mut fields = text.split('/');
+        let position = line.index(fields.next().unwrap_or(""), self.positions.len(), "posición")?;
+        let tex_coord = match fields.next() {
+            None | Some("") => None,
+            Some(field) => Some(line.index(field, self.tex_coords.len(), "textura")?),
+        };
+        let normal = match fields.next() {
+            None | Some("") => None,
+            Some(field) => Some(line.index(field, self.normals.len(), "normal")?),
+        };
+        if fields.next().is_some() {
+            return Err(line.error(format!("vértice de cara inválido '{}'", text)));
+        }
+        Ok(Corner {
+            position,
+            tex_coord,
+            normal,
         })
     }
 
-    pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertex_array = Vec::new();
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+
+    // Distancia del origen al vértice más lejano
+    pub fn bounding_radius(&self) -> f32 {
+        self.positions.iter().map(|p| p.norm()).fold(0.0, f32::max)
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(|group| group.triangles.len()).sum()
+    }
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.display().to_string(),
+        source,
+    })
+}
 
-        for face in &self.faces {
-            for i in 0..3 {
-                let pos_idx = face[i * 3];
-                let tex_idx = face[i * 3 + 1];
-                let norm_idx = face[i * 3 + 2];
+// Triángulos (índices al polígono) que cubren un polígono plano de n vértices,
+// por recorte de orejas sobre su proyección en el plano del polígono. Conserva
+// el sentido de giro de la cara y funciona con polígonos cóncavos; si el
+// polígono es degenerado cae a un abanico desde el primer vértice.
+fn triangulate(polygon: &[Vec3]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    let fan = || (1..n - 1).map(|i| [0, i, i + 1]).collect();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Normal de Newell: estable aunque haya vértices alineados
+    let mut normal = Vec3::zeros();
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        normal += Vec3::new(
+            (p.y - q.y) * (p.z + q.z),
+            (p.z - q.z) * (p.x + q.x),
+            (p.x - q.x) * (p.y + q.y),
+        );
+    }
+    let Some(normal) = normal.try_normalize(1e-12) else {
+        return fan();
+    };
+    let u = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    let u = (u - normal * normal.dot(&u)).normalize();
+    let v = normal.cross(&u);
+    let points: Vec<Vec2> = polygon.iter().map(|p| Vec2::new(p.dot(&u), p.dot(&v))).collect();
+
+    // En esta base el polígono siempre gira en sentido antihorario
+    let cross = |a: Vec2, b: Vec2, c: Vec2| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = (remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            if cross(pa, pb, pc) <= 0.0 {
+                return false;
+            }
+            // Ningún otro vértice dentro de la oreja
+            remaining.iter().all(|&j| {
+                j == a || j == b || j == c || {
+                    let p = points[j];
+                    cross(pa, pb, p) < 0.0 || cross(pb, pc, p) < 0.0 || cross(pc, pa, p) < 0.0
+                }
+            })
+        });
+        let Some(i) = ear else {
+            return fan();
+        };
+        triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
 
-                let position = self.vertices.get(pos_idx).copied().unwrap_or(Vec3::zeros());
-                let tex_coords = self.tex_coords.get(tex_idx).copied().unwrap_or(Vec3::zeros());
-                let normal = self.normals.get(norm_idx).copied().unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+// Materiales de un archivo MTL
+pub fn parse_mtl(source: &str, origin: &str) -> Result<Vec<Material>, ObjError> {
+    let mut materials: Vec<Material> = Vec::new();
+    for (number, text) in logical_lines(source) {
+        let line = Line { origin, number };
+        let mut parts = text.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let args: Vec<&str> = parts.collect();
 
-                vertex_array.push(Vertex::new(position, normal, tex_coords));
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(line.error("'newmtl' sin nombre".to_string()));
             }
+            materials.push(Material::new(&args.join(" ")));
+            continue;
         }
+        let Some(material) = materials.last_mut() else {
+            return Err(line.error(format!("'{}' antes de cualquier 'newmtl'", keyword)));
+        };
+        match keyword {
+            "Ka" => material.ambient = line.color(keyword, &args)?,
+            "Kd" => material.diffuse = line.color(keyword, &args)?,
+            "Ks" => material.specular = line.color(keyword, &args)?,
+            "Ke" => material.emissive = line.color(keyword, &args)?,
+            "Ns" => material.shininess = line.floats(keyword, &args, 1, 1)?[0],
+            "d" => material.opacity = line.floats(keyword, &args, 1, 1)?[0],
+            "Tr" => material.opacity = 1.0 - line.floats(keyword, &args, 1, 1)?[0],
+            // Las opciones (-s, -o, ...) van antes; el archivo es lo último
+            "map_Kd" => match args.last() {
+                Some(file) => material.diffuse_map = Some(file.to_string()),
+                None => return Err(line.error("'map_Kd' sin archivo".to_string())),
+            },
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_line(source: &str) -> usize {
+        match Obj::parse(source, "test.obj") {
+            Err(ObjError::Invalid { line, .. }) => line,
+            other => panic!("se esperaba un error, se obtuvo {:?}", other.map(|obj| obj.groups)),
+        }
+    }
+
+    #[test]
+    fn polygons_are_triangulated() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 1 0\nf 1 2 3 4 5\n";
+        let obj = Obj::parse(source, "test.obj").unwrap();
+        assert_eq!(obj.triangle_count(), 3);
+
+        // Todos conservan el giro antihorario de la cara (normal +z)
+        for triangle in &obj.groups[0].triangles {
+            let [a, b, c] = triangle.map(|corner| obj.positions[corner.position]);
+            assert!((b - a).cross(&(c - a)).z > 0.0);
+        }
+    }
 
-        vertex_array
+    #[test]
+    fn concave_polygons_stay_inside() {
+        // Una "L": el abanico desde el vértice 0 saldría del polígono
+        let polygon = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]
+            .map(|(x, y)| Vec3::new(x, y, 0.0));
+        let triangles = triangulate(&polygon);
+        assert_eq!(triangles.len(), 4);
+        let area: f32 = triangles
+            .iter()
+            .map(|&[a, b, c]| (polygon[b] - polygon[a]).cross(&(polygon[c] - polygon[a])).z / 2.0)
+            .sum();
+        assert!((area - 3.0).abs() < 1e-5, "{area}");
+    }
+
+    #[test]
+    fn relative_indices_and_groups() {
+        let source = "\
+mtllib scene.mtl
+o roca
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 0 0 1
+f -3//-1 -2//-1 -1//-1
+usemtl piedra
+g cara b
+v 0 0 1
+f 1 2 4
+";
+        let obj = Obj::parse(source, "test.obj").unwrap();
+        assert_eq!(obj.material_libraries, ["scene.mtl"]);
+        assert_eq!(obj.groups.len(), 2);
+        assert_eq!(obj.groups[0].name, "roca");
+        assert_eq!(obj.groups[0].material, None);
+        let corner = obj.groups[0].triangles[0][0];
+        assert_eq!(corner, Corner { position: 0, tex_coord: None, normal: Some(0) });
+        assert_eq!(obj.groups[1].name, "cara b");
+        assert_eq!(obj.groups[1].material.as_deref(), Some("piedra"));
+        assert_eq!(obj.groups[1].triangles[0][2].position, 3);
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        assert_eq!(invalid_line("v 0 0 0\nv 1 x 0\n"), 2);
+        assert_eq!(invalid_line("v 0 0\n"), 1);
+        assert_eq!(invalid_line("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n"), 4);
+        assert_eq!(invalid_line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 0\n"), 4);
+        assert_eq!(invalid_line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -4 -2 -1\n"), 4);
+        assert_eq!(invalid_line("v 0 0 0\nv 1 0 0\nf 1 2\n"), 3);
+        assert_eq!(invalid_line("# comentario\nbanana 1 2\n"), 2);
+    }
+
+    #[test]
+    fn continued_lines_keep_their_first_number() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 \\\n 3\nf 1 2 9\n";
+        assert_eq!(invalid_line(source), 6);
+    }
+
+    #[test]
+    fn load_reads_material_libraries_next_to_the_obj() {
+        let directory = std::env::temp_dir().join(format!("obj_loader_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let faces = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        std::fs::write(directory.join("roca.mtl"), "newmtl piedra\nKd 0.5 0.5 0.5\n").unwrap();
+        std::fs::write(directory.join("bien.obj"), format!("mtllib roca.mtl\nusemtl piedra\n{faces}")).unwrap();
+        std::fs::write(directory.join("mal.obj"), format!("mtllib roca.mtl\n{faces}usemtl hielo\n")).unwrap();
+
+        let obj = Obj::load(&directory.join("bien.obj")).unwrap();
+        assert_eq!(obj.material("piedra").map(|m| m.diffuse), Some([0.5; 3]));
+        let error = Obj::load(&directory.join("mal.obj")).unwrap_err();
+        assert!(matches!(error, ObjError::Invalid { line: 6, .. }), "{error}");
+        assert!(matches!(
+            Obj::load(&directory.join("no_existe.obj")),
+            Err(ObjError::Io { .. })
+        ));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn mtl_materials() {
+        let source = "\
+# exportado
+newmtl piedra
+Kd 0.5 0.4 0.3
+Ks 1
+Ns 10
+Tr 0.25
+map_Kd -s 2 2 1 textures/piedra.png
+newmtl hielo
+d 0.5
+";
+        let materials = parse_mtl(source, "test.mtl").unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].diffuse, [0.5, 0.4, 0.3]);
+        assert_eq!(materials[0].specular, [1.0; 3]);
+        assert_eq!(materials[0].shininess, 10.0);
+        assert_eq!(materials[0].opacity, 0.75);
+        assert_eq!(materials[0].diffuse_map.as_deref(), Some("textures/piedra.png"));
+        assert_eq!(materials[1].opacity, 0.5);
+
+        match parse_mtl("Kd 1 1 1\n", "test.mtl") {
+            Err(ObjError::Invalid { line: 1, .. }) => {}
+            other => panic!("{:?}", other),
+        }
     }
 }
@@ -0,0 +1,171 @@
+// orbit.rs - Mecánica orbital kepleriana resuelta en CPU
+//
+// El plano de referencia (eclíptica) es el plano x-z del mundo, con y hacia arriba.
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::{PI, TAU};
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    // Ángulos en radianes
+    pub inclination: f32,
+    pub ascending_node: f32,
+    pub argument_of_periapsis: f32,
+    pub period: f32,
+    // Anomalía media en t = 0
+    pub phase: f32,
+}
+
+// Resuelve M = E - e sin(E) para la anomalía excéntrica E con Newton-Raphson
+pub fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let m = mean_anomaly.rem_euclid(TAU);
+    let mut e_anom = if eccentricity < 0.8 { m } else { PI };
+
+    for _ in 0..32 {
+        let f = e_anom - eccentricity * e_anom.sin() - m;
+        let df = 1.0 - eccentricity * e_anom.cos();
+        let step = f / df;
+        e_anom -= step;
+        if step.abs() < 1e-7 {
+            break;
+        }
+    }
+
+    e_anom
+}
+
+impl OrbitalElements {
+    pub fn mean_motion(&self) -> f32 {
+        TAU / self.period
+    }
+
+    pub fn position(&self, time: f32) -> Vec3 {
+        self.state(time).0
+    }
+
+    // Posición y velocidad relativas al cuerpo central en el instante `time`
+    pub fn state(&self, time: f32) -> (Vec3, Vec3) {
+        let a = self.semi_major_axis;
+        let e = self.eccentricity;
+        let b = a * (1.0 - e * e).sqrt();
+
+        let mean_anomaly = self.phase + self.mean_motion() * time;
+        let e_anom = solve_kepler(mean_anomaly, e);
+        let (sin_e, cos_e) = e_anom.sin_cos();
+        let e_dot = self.mean_motion() / (1.0 - e * cos_e);
+
+        // Coordenadas en el plano de la órbita, con el periapsis sobre +x
+        let position = (a * (cos_e - e), b * sin_e);
+        let velocity = (-a * sin_e * e_dot, b * cos_e * e_dot);
+
+        (self.perifocal_to_world(position), self.perifocal_to_world(velocity))
+    }
+
+    fn perifocal_to_world(&self, (xp, yp): (f32, f32)) -> Vec3 {
+        let (sin_o, cos_o) = self.ascending_node.sin_cos();
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        let x = xp * (cos_o * cos_w - sin_o * sin_w * cos_i) - yp * (cos_o * sin_w + sin_o * cos_w * cos_i);
+        let y = xp * (sin_o * cos_w + cos_o * sin_w * cos_i) + yp * (cos_o * cos_w * cos_i - sin_o * sin_w);
+        let z = xp * (sin_w * sin_i) + yp * (cos_w * sin_i);
+
+        // Eclíptica con z al norte -> mundo con y hacia arriba
+        Vec3::new(x, z, -y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elliptical() -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: 1.5,
+            eccentricity: 0.6,
+            inclination: 0.3,
+            ascending_node: 1.1,
+            argument_of_periapsis: 0.7,
+            period: 12.0,
+            phase: 0.4,
+        }
+    }
+
+    #[test]
+    fn kepler_solution_satisfies_equation() {
+        for &e in &[0.0, 0.1, 0.5, 0.9, 0.97] {
+            for i in 0..64 {
+                let m = i as f32 * TAU / 64.0;
+                let e_anom = solve_kepler(m, e);
+                let residual = e_anom - e * e_anom.sin() - m;
+                assert!(residual.abs() < 1e-5, "e={} M={} residual={}", e, m, residual);
+            }
+        }
+    }
+
+    #[test]
+    fn position_repeats_after_one_period() {
+        let orbit = elliptical();
+        for i in 0..10 {
+            let t = i as f32 * 0.83;
+            let p0 = orbit.position(t);
+            let p1 = orbit.position(t + orbit.period);
+            assert!((p0 - p1).norm() < 1e-4, "t={} {:?} != {:?}", t, p0, p1);
+        }
+    }
+
+    #[test]
+    fn energy_is_conserved() {
+        let orbit = elliptical();
+        // GM implícito en el periodo (tercera ley de Kepler) y energía específica esperada
+        let n = orbit.mean_motion();
+        let mu = n * n * orbit.semi_major_axis.powi(3);
+        let expected = -mu / (2.0 * orbit.semi_major_axis);
+
+        for i in 0..200 {
+            let t = i as f32 * orbit.period / 200.0;
+            let (r, v) = orbit.state(t);
+            let energy = v.norm_squared() / 2.0 - mu / r.norm();
+            assert!(
+                ((energy - expected) / expected).abs() < 1e-3,
+                "t={} energy={} expected={}",
+                t,
+                energy,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn periapsis_and_apoapsis_distances() {
+        let mut orbit = elliptical();
+        orbit.phase = 0.0;
+        let a = orbit.semi_major_axis;
+        let e = orbit.eccentricity;
+
+        let periapsis = orbit.position(0.0).norm();
+        let apoapsis = orbit.position(orbit.period / 2.0).norm();
+        assert!((periapsis - a * (1.0 - e)).abs() < 1e-5);
+        assert!((apoapsis - a * (1.0 + e)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn circular_orbit_stays_on_the_ecliptic() {
+        let orbit = OrbitalElements {
+            semi_major_axis: 2.0,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            period: 5.0,
+            phase: 0.0,
+        };
+        for i in 0..20 {
+            let p = orbit.position(i as f32 * 0.37);
+            assert!((p.norm() - 2.0).abs() < 1e-5);
+            assert!(p.y.abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// parity.rs - Compara los shaders de CPU (shaders.rs) con los de GPU (shader.wgsl)
+//
+// Los mismos tipos de cuerpo están implementados dos veces y se fueron
+// separando. Esta verificación evalúa ambos en los mismos puntos de una esfera
+// unitaria (posición local = normal, sin tinte, con la luz direccional de
+// respaldo de `Light::fallback`) al mismo tiempo de simulación:
+// la GPU con un compute shader que llama a `shade_body` y la CPU con
+// `fragment_shader`. Por cada tipo informa el color medio de cada lado y la
+// diferencia ΔE (CIELAB) punto a punto, ya en sRGB como se ve en pantalla.
+
+use crate::fragment::Fragment;
+use crate::headless;
+use crate::renderer::wgsl_with_prelude;
+use crate::shader_kind::ShaderKind;
+use crate::shaders::fragment_shader;
+use crate::simulation::{Light, LightSource};
+use crate::software::Uniforms;
+use nalgebra_glm::{Mat4, Vec3};
+use sistema::cielab::delta_e;
+use wgpu::util::DeviceExt;
+
+const SAMPLES: u32 = 4096;
+const WORKGROUP_SIZE: u32 = 64;
+// Los shaders de CPU cuentan el tiempo en cuadros
+const FRAME_RATE: f32 = 60.0;
+// Mismo umbral que las pruebas golden: por encima la diferencia ya se nota
+const MAX_DELTA_E: f32 = 6.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParityUniforms {
+    light_dir: [f32; 4],
+    shader_type: u32,
+    time: f32,
+    _padding: [f32; 2],
+}
+
+pub struct Report {
+    pub kind: ShaderKind,
+    pub gpu_mean: [u8; 3],
+    // None si el tipo no tiene versión en CPU
+    pub cpu: Option<Difference>,
+}
+
+pub struct Difference {
+    pub cpu_mean: [u8; 3],
+    pub mean_delta_e: f32,
+    pub max_delta_e: f32,
+    // Fracción de puntos con ΔE > MAX_DELTA_E
+    pub different: f32,
+}
+
+// Puntos repartidos uniformemente en la esfera (espiral de Fibonacci)
+fn sphere_points(count: u32) -> Vec<Vec3> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let r = (1.0 - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            Vec3::new(r * theta.cos(), y, r * theta.sin())
+        })
+        .collect()
+}
+
+pub fn run(time: f32) -> Result<Vec<Report>, String> {
+    let points = sphere_points(SAMPLES);
+    let gpu = pollster::block_on(GpuSampler::new(&points))?;
+
+    let uniforms = Uniforms {
+        model_matrix: Mat4::identity(),
+        view_matrix: Mat4::identity(),
+        projection_matrix: Mat4::identity(),
+        viewport_matrix: Mat4::identity(),
+        time: (time * FRAME_RATE).round() as u32,
+        light: Light::fallback(),
+        rings: None,
+        occluders: &[],
+    };
+
+    let mut reports = Vec::new();
+    for kind in ShaderKind::for_bodies() {
+        let gpu_colors: Vec<[u8; 3]> = gpu
+            .sample(kind, time)
+            .iter()
+            .map(|c| [0, 1, 2].map(|i| (linear_to_srgb(c[i]) * 255.0).round() as u8))
+            .collect();
+
+        let cpu_colors: Option<Vec<[u8; 3]>> = points
+            .iter()
+            .map(|&p| {
+                let fragment = Fragment::new(Vec3::zeros(), p, 0.0, p);
+                fragment_shader(&fragment, &uniforms, kind).map(|c| [c.r, c.g, c.b])
+            })
+            .collect();
+
+        let cpu = cpu_colors.map(|cpu_colors| {
+            let deltas: Vec<f32> = cpu_colors
+                .iter()
+                .zip(&gpu_colors)
+                .map(|(c, g)| delta_e(c, g))
+                .collect();
+            Difference {
+                cpu_mean: mean(&cpu_colors),
+                mean_delta_e: deltas.iter().sum::<f32>() / deltas.len() as f32,
+                max_delta_e: deltas.iter().cloned().fold(0.0, f32::max),
+                different: deltas.iter().filter(|&&d| d > MAX_DELTA_E).count() as f32
+                    / deltas.len() as f32,
+            }
+        });
+
+        reports.push(Report {
+            kind,
+            gpu_mean: mean(&gpu_colors),
+            cpu,
+        });
+    }
+
+    Ok(reports)
+}
+
+pub fn print(reports: &[Report], time: f32) {
+    println!(
+        "Paridad CPU/GPU en {} puntos de la esfera, t = {} s (ΔE > {} se nota)",
+        SAMPLES, time, MAX_DELTA_E
+    );
+    println!(
+        "{:<14} {:>9} {:>9} {:>9} {:>9} {:>10}",
+        "shader", "CPU", "GPU", "ΔE medio", "ΔE máx", "distintos"
+    );
+    let hex = |c: [u8; 3]| format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2]);
+    for report in reports {
+        let label = format!("{} ({})", report.kind, report.kind.id());
+        match &report.cpu {
+            Some(d) => println!(
+                "{:<14} {:>9} {:>9} {:>9.1} {:>9.1} {:>9.1}%",
+                label,
+                hex(d.cpu_mean),
+                hex(report.gpu_mean),
+                d.mean_delta_e,
+                d.max_delta_e,
+                d.different * 100.0
+            ),
+            None => println!(
+                "{:<14} {:>9} {:>9}  sólo existe en GPU",
+                label,
+                "-",
+                hex(report.gpu_mean)
+            ),
+        }
+    }
+}
+
+fn mean(colors: &[[u8; 3]]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    for color in colors {
+        for i in 0..3 {
+            sum[i] += color[i] as u64;
+        }
+    }
+    sum.map(|s| (s / colors.len().max(1) as u64) as u8)
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+// Compute pipeline sobre shader.wgsl + parity.wgsl con los puntos ya subidos
+struct GpuSampler {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    uniform_buffer: wgpu::Buffer,
+    color_buffer: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // Los grupos 0 y 1 de shader.wgsl quedan vacíos en este pipeline, pero hay que fijarlos igual
+    empty_groups: [wgpu::BindGroup; 2],
+    count: u32,
+}
+
+impl GpuSampler {
+    async fn new(points: &[Vec3]) -> Result<Self, String> {
+        let (device, queue) = headless::request_device().await?;
+
+        let source = wgsl_with_prelude(&format!(
+            "{}\n{}",
+            include_str!("shader.wgsl"),
+            include_str!("parity.wgsl")
+        ));
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Parity Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Parity Pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: "parity_main",
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Parity Uniforms"),
+            size: std::mem::size_of::<ParityUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let padded: Vec<[f32; 4]> = points.iter().map(|p| [p.x, p.y, p.z, 0.0]).collect();
+        let point_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Parity Points"),
+            contents: bytemuck::cast_slice(&padded),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let size = std::mem::size_of_val(padded.as_slice()) as wgpu::BufferAddress;
+        let color_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Parity Colors"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Parity Readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Parity Bind Group"),
+            layout: &pipeline.get_bind_group_layout(2),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: point_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: color_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let empty_groups = [0, 1].map(|index| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Parity Empty Bind Group"),
+                layout: &pipeline.get_bind_group_layout(index),
+                entries: &[],
+            })
+        });
+
+        Ok(GpuSampler {
+            device,
+            queue,
+            pipeline,
+            uniform_buffer,
+            color_buffer,
+            readback,
+            bind_group,
+            empty_groups,
+            count: points.len() as u32,
+        })
+    }
+
+    // Colores lineales de `shade_body` en cada punto
+    fn sample(&self, kind: ShaderKind, time: f32) -> Vec<[f32; 4]> {
+        let LightSource::Directional(light_dir) = Light::fallback().source else {
+            unreachable!("la luz de respaldo es direccional");
+        };
+        let uniforms = ParityUniforms {
+            light_dir: [light_dir.x, light_dir.y, light_dir.z, 0.0],
+            shader_type: kind.id(),
+            time,
+            _padding: [0.0; 2],
+        };
+        self.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Parity Encoder"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Parity Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            for (index, group) in self.empty_groups.iter().enumerate() {
+                pass.set_bind_group(index as u32, group, &[]);
+            }
+            pass.set_bind_group(2, &self.bind_group, &[]);
+            pass.dispatch_workgroups(self.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.color_buffer, 0, &self.readback, 0, self.readback.size());
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        self.device.poll(wgpu::Maintain::Wait);
+        let colors = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        colors
+    }
+}
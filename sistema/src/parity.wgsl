@@ -0,0 +1,30 @@
+// parity.wgsl - Evalúa shade_body de shader.wgsl en puntos dados
+//
+// parity.rs lo concatena al final de shader.wgsl. Cada invocación toma un punto
+// de la esfera unitaria (que también es su normal) y escribe el color lineal.
+
+struct Parity {
+    light_dir: vec4<f32>,
+    shader_type: u32,
+    time: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> parity: Parity;
+
+@group(2) @binding(1)
+var<storage, read> parity_points: array<vec4<f32>>;
+
+@group(2) @binding(2)
+var<storage, read_write> parity_colors: array<vec4<f32>>;
+
+@compute @workgroup_size(64)
+fn parity_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= arrayLength(&parity_points)) {
+        return;
+    }
+    let pos = parity_points[id.x].xyz;
+    let light = LightSample(parity.light_dir.xyz, 1.0);
+    let shaded = shade_body(parity.shader_type, pos, pos, light, parity.time);
+    parity_colors[id.x] = vec4<f32>(shaded.color + shaded.emission, 1.0);
+}
@@ -0,0 +1,471 @@
+// post.rs - Imagen HDR, bloom y mapeo de tonos antes de presentar
+//
+// La escena se dibuja en dos texturas de punto flotante: el color final de cada
+// pixel y, aparte, sólo la luz emitida (sol, lava). La emisión se difumina con
+// una cadena de reducciones y ampliaciones (bloom) y la pasada final suma ambas,
+// aplica la exposición y el operador de tonos elegido y escribe en el destino
+// (superficie de la ventana o textura de headless).
+
+use crate::renderer::wgsl_with_prelude;
+use std::str::FromStr;
+
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// Cantidad máxima de reducciones a la mitad; con 5 el halo llega a ~1/16 de la pantalla
+const BLOOM_LEVELS: u32 = 5;
+const EXPOSURE: f32 = 1.0;
+const BLOOM_STRENGTH: f32 = 0.6;
+
+// Cómo se comprime el rango HDR a [0, 1]; post.wgsl usa las constantes TONEMAP_*
+// que genera `wgsl_constants`
+#[repr(u32)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemap {
+    #[default]
+    Aces = 0,
+    Reinhard = 1,
+}
+
+impl Tonemap {
+    pub const ALL: [Tonemap; 2] = [Tonemap::Aces, Tonemap::Reinhard];
+
+    pub fn next(self) -> Tonemap {
+        match self {
+            Tonemap::Aces => Tonemap::Reinhard,
+            Tonemap::Reinhard => Tonemap::Aces,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tonemap::Aces => "aces",
+            Tonemap::Reinhard => "reinhard",
+        }
+    }
+
+    // Valor de `PostUniforms.tonemap` en post.wgsl
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    // `const TONEMAP_ACES: u32 = 0u;` etc., para agregar a los .wgsl
+    pub fn wgsl_constants() -> String {
+        let mut block = String::from("// Generado por Tonemap::wgsl_constants (post.rs)\n");
+        for tonemap in Self::ALL {
+            block.push_str(&format!(
+                "const TONEMAP_{}: u32 = {}u;\n",
+                tonemap.name().to_uppercase(),
+                tonemap.id()
+            ));
+        }
+        block
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "aces" => Ok(Tonemap::Aces),
+            "reinhard" => Ok(Tonemap::Reinhard),
+            _ => Err(format!("tonemap desconocido '{}' (esperado: aces, reinhard)", name)),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniforms {
+    exposure: f32,
+    bloom_strength: f32,
+    tonemap: u32,
+    _padding: f32,
+}
+
+// Texturas que dependen del tamaño; se recrean en cada resize
+struct Targets {
+    color_view: wgpu::TextureView,
+    emission_view: wgpu::TextureView,
+    // Niveles de bloom de mayor a menor: la mitad del tamaño, un cuarto, ...
+    bloom_views: Vec<wgpu::TextureView>,
+    // downsample_groups[i] lee el nivel anterior (la emisión para i = 0) y
+    // upsample_groups[i] lee bloom_views[i + 1]
+    downsample_groups: Vec<wgpu::BindGroup>,
+    upsample_groups: Vec<wgpu::BindGroup>,
+    tonemap_group: wgpu::BindGroup,
+}
+
+pub struct PostProcess {
+    pub tonemap: Tonemap,
+    sampler: wgpu::Sampler,
+    sample_layout: wgpu::BindGroupLayout,
+    tonemap_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    tonemap_pipeline: wgpu::RenderPipeline,
+    targets: Targets,
+}
+
+fn create_hdr_view(device: &wgpu::Device, label: &str, width: u32, height: u32) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn sampler_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }
+}
+
+fn fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::BindGroupLayout,
+    entry_point: &str,
+    format: wgpu::TextureFormat,
+    blend: wgpu::BlendState,
+) -> wgpu::RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_fullscreen",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+impl PostProcess {
+    // `output_format` es el del destino final; las texturas intermedias son HDR_FORMAT
+    pub fn new(device: &wgpu::Device, output_format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let sample_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post_sample_layout"),
+            entries: &[texture_entry(0), sampler_entry(1)],
+        });
+        let tonemap_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post_tonemap_layout"),
+            entries: &[
+                texture_entry(0),
+                sampler_entry(1),
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Uniform Buffer"),
+            size: std::mem::size_of::<PostUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Shader"),
+            source: wgpu::ShaderSource::Wgsl(wgsl_with_prelude(include_str!("post.wgsl")).into()),
+        });
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+        let downsample_pipeline = fullscreen_pipeline(
+            device,
+            "Bloom Downsample",
+            &shader,
+            &sample_layout,
+            "fs_downsample",
+            HDR_FORMAT,
+            wgpu::BlendState::REPLACE,
+        );
+        let upsample_pipeline = fullscreen_pipeline(
+            device,
+            "Bloom Upsample",
+            &shader,
+            &sample_layout,
+            "fs_upsample",
+            HDR_FORMAT,
+            additive,
+        );
+        let tonemap_pipeline = fullscreen_pipeline(
+            device,
+            "Tonemap",
+            &shader,
+            &tonemap_layout,
+            "fs_tonemap",
+            output_format,
+            wgpu::BlendState::REPLACE,
+        );
+
+        let targets = Self::create_targets(
+            device,
+            &sampler,
+            &sample_layout,
+            &tonemap_layout,
+            &uniform_buffer,
+            width,
+            height,
+        );
+
+        PostProcess {
+            tonemap: Tonemap::default(),
+            sampler,
+            sample_layout,
+            tonemap_layout,
+            uniform_buffer,
+            downsample_pipeline,
+            upsample_pipeline,
+            tonemap_pipeline,
+            targets,
+        }
+    }
+
+    fn create_targets(
+        device: &wgpu::Device,
+        sampler: &wgpu::Sampler,
+        sample_layout: &wgpu::BindGroupLayout,
+        tonemap_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        width: u32,
+        height: u32,
+    ) -> Targets {
+        let color_view = create_hdr_view(device, "HDR Color", width, height);
+        let emission_view = create_hdr_view(device, "HDR Emission", width, height);
+
+        let mut bloom_views = Vec::new();
+        let (mut w, mut h) = (width, height);
+        while bloom_views.len() < BLOOM_LEVELS as usize && (w > 1 || h > 1) {
+            (w, h) = ((w / 2).max(1), (h / 2).max(1));
+            bloom_views.push(create_hdr_view(device, "Bloom Level", w, h));
+        }
+
+        let sample_group = |view: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("post_sample_group"),
+                layout: sample_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        };
+        let downsample_groups = std::iter::once(&emission_view)
+            .chain(&bloom_views)
+            .take(bloom_views.len())
+            .map(sample_group)
+            .collect();
+        let upsample_groups = bloom_views.iter().skip(1).map(sample_group).collect();
+
+        // Una imagen de 1x1 no tiene niveles de bloom: se usa la emisión sin difuminar
+        let bloom_source = bloom_views.first().unwrap_or(&emission_view);
+        let tonemap_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post_tonemap_group"),
+            layout: tonemap_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&color_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(bloom_source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Targets {
+            color_view,
+            emission_view,
+            bloom_views,
+            downsample_groups,
+            upsample_groups,
+            tonemap_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets = Self::create_targets(
+            device,
+            &self.sampler,
+            &self.sample_layout,
+            &self.tonemap_layout,
+            &self.uniform_buffer,
+            width,
+            height,
+        );
+    }
+
+    // Destinos de la pasada de escena: color en @location(0), emisión en @location(1)
+    pub fn color_view(&self) -> &wgpu::TextureView {
+        &self.targets.color_view
+    }
+
+    pub fn emission_view(&self) -> &wgpu::TextureView {
+        &self.targets.emission_view
+    }
+
+    // Bloom sobre la emisión y mapeo de tonos hacia `output`
+    pub fn run(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let uniforms = PostUniforms {
+            exposure: EXPOSURE,
+            bloom_strength: BLOOM_STRENGTH,
+            tonemap: self.tonemap as u32,
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let targets = &self.targets;
+        for (view, group) in targets.bloom_views.iter().zip(&targets.downsample_groups) {
+            fullscreen_pass(encoder, "Bloom Downsample", view, wgpu::LoadOp::Clear(wgpu::Color::BLACK), &self.downsample_pipeline, group);
+        }
+        // De menor a mayor, sumando cada nivel sobre el siguiente más grande
+        for (view, group) in targets.bloom_views.iter().zip(&targets.upsample_groups).rev() {
+            fullscreen_pass(encoder, "Bloom Upsample", view, wgpu::LoadOp::Load, &self.upsample_pipeline, group);
+        }
+        fullscreen_pass(
+            encoder,
+            "Tonemap",
+            output,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            &self.tonemap_pipeline,
+            &targets.tonemap_group,
+        );
+    }
+}
+
+fn fullscreen_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    label: &str,
+    view: &wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+    pipeline: &wgpu::RenderPipeline,
+    group: &wgpu::BindGroup,
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, group, &[]);
+    pass.draw(0..3, 0..1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tonemap_parses_names_and_cycles() {
+        for tonemap in Tonemap::ALL {
+            assert_eq!(tonemap.name().parse::<Tonemap>(), Ok(tonemap));
+            assert_eq!(tonemap.next().next(), tonemap);
+        }
+        assert!("filmic".parse::<Tonemap>().unwrap_err().contains("filmic"));
+    }
+
+    #[test]
+    fn ids_are_unique_and_match_constants() {
+        let constants = Tonemap::wgsl_constants();
+        for (i, tonemap) in Tonemap::ALL.iter().enumerate() {
+            assert!(Tonemap::ALL[..i].iter().all(|other| other.id() != tonemap.id()));
+            let line = format!("const TONEMAP_{}: u32 = {}u;", tonemap.name().to_uppercase(), tonemap.id());
+            assert!(constants.contains(&line), "{}", line);
+        }
+    }
+}
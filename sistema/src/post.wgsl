@@ -0,0 +1,103 @@
+// post.wgsl - Bloom y mapeo de tonos sobre la imagen HDR
+//
+// Todas las pasadas dibujan un triángulo que cubre la pantalla. El bloom parte
+// del canal de emisión (sol, lava, estrellas de la escena): se reduce a la mitad
+// varias veces y se vuelve a ampliar sumando cada nivel, lo que da un halo ancho
+// sin un kernel enorme. Al final `fs_tonemap` junta color y bloom y los lleva a
+// [0, 1] con ACES o Reinhard.
+
+// Las constantes TONEMAP_* las genera Tonemap::wgsl_constants (post.rs) y se
+// agregan al final del código con renderer::wgsl_with_prelude
+
+struct PostUniforms {
+    exposure: f32,
+    bloom_strength: f32,
+    tonemap: u32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@group(0) @binding(1)
+var linear_sampler: sampler;
+
+// Sólo en la pasada final
+@group(0) @binding(2)
+var bloom: texture_2d<f32>;
+
+@group(0) @binding(3)
+var<uniform> post: PostUniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // (0, 0), (2, 0), (0, 2) en uv: un triángulo que cubre todo el viewport
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+// Cuatro muestras bilineales en las esquinas del texel de destino: promedia un
+// bloque de 4x4 texeles de la fuente
+@fragment
+fn fs_downsample(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    var color = vec3<f32>(0.0);
+    color += textureSample(source, linear_sampler, input.uv + texel * vec2<f32>(-1.0, -1.0)).rgb;
+    color += textureSample(source, linear_sampler, input.uv + texel * vec2<f32>(1.0, -1.0)).rgb;
+    color += textureSample(source, linear_sampler, input.uv + texel * vec2<f32>(-1.0, 1.0)).rgb;
+    color += textureSample(source, linear_sampler, input.uv + texel * vec2<f32>(1.0, 1.0)).rgb;
+    return vec4<f32>(color * 0.25, 1.0);
+}
+
+// Filtro tienda de 3x3 sobre el nivel más chico; se suma (blend aditivo) al
+// nivel de destino
+@fragment
+fn fs_upsample(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    var color = vec3<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+            let offset = texel * vec2<f32>(f32(x), f32(y));
+            color += textureSample(source, linear_sampler, input.uv + offset).rgb * weight;
+        }
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// Ajuste de Narkowicz a la curva ACES de cine
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Reinhard sobre la luminancia, para no desaturar los colores intensos
+fn reinhard(x: vec3<f32>) -> vec3<f32> {
+    let luminance = dot(x, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return clamp(x / (1.0 + luminance), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Devuelve color lineal: el destino sRGB hace la codificación
+@fragment
+fn fs_tonemap(input: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(source, linear_sampler, input.uv).rgb;
+    let glow = textureSample(bloom, linear_sampler, input.uv).rgb;
+    let color = (hdr + glow * post.bloom_strength) * post.exposure;
+
+    switch post.tonemap {
+        case TONEMAP_REINHARD: { return vec4<f32>(reinhard(color), 1.0); }
+        default: { return vec4<f32>(aces(color), 1.0); }
+    }
+}
@@ -0,0 +1,975 @@
+// renderer.rs - Dibujo de la escena con wgpu, independiente de la ventana
+//
+// main.rs lo usa con la superficie de la ventana y headless.rs con una textura
+// fuera de pantalla; cada cuadro dibuja el estado actual de la `Simulation`.
+
+use crate::atmosphere::Atmospheres;
+use crate::obj_loader::Obj;
+use crate::post::{HDR_FORMAT, PostProcess, Tonemap};
+use crate::scene::Scene;
+use crate::shader_kind::ShaderKind;
+use crate::simulation::{LightSource, MAX_OCCLUDERS, Simulation};
+use crate::starfield::{Star, Starfield};
+use nalgebra_glm::{self as glm, Mat4, Vec2, Vec3};
+use sistema::meshgen::{self, MeshData};
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    sky_view_proj: [[f32; 4]; 4], // Rotation only, for the star background
+    time: f32,
+    debug_view: u32,
+    resolution: [f32; 2],
+    light: [f32; 4], // xyz: sun position (w = 1) or direction toward the light (w = 0)
+    light_intensity: f32,
+    light_inverse_square: u32,
+    light_radius: f32,
+    occluder_count: u32,
+    occluders: [[f32; 4]; MAX_OCCLUDERS], // xyz: center, w: radius
+}
+
+// WGSL type of each Uniforms field, in the same order. The WGSL declaration is
+// generated from this list, so the .wgsl files don't keep their own copy.
+const UNIFORM_FIELDS: [(&str, &str); 11] = [
+    ("view_proj", "mat4x4<f32>"),
+    ("sky_view_proj", "mat4x4<f32>"),
+    ("time", "f32"),
+    ("debug_view", "u32"),
+    ("resolution", "vec2<f32>"),
+    ("light", "vec4<f32>"),
+    ("light_intensity", "f32"),
+    ("light_inverse_square", "u32"),
+    ("light_radius", "f32"),
+    ("occluder_count", "u32"),
+    ("occluders", "array<vec4<f32>, MAX_OCCLUDERS>"),
+];
+
+// `struct Uniforms` and MAX_OCCLUDERS for every shader that binds group 0
+pub fn uniforms_wgsl() -> String {
+    let mut block = String::from("// Generado por renderer::uniforms_wgsl (renderer.rs)\n");
+    block.push_str(&format!("const MAX_OCCLUDERS: u32 = {}u;\n", MAX_OCCLUDERS));
+    block.push_str("struct Uniforms {\n");
+    for (name, ty) in UNIFORM_FIELDS {
+        block.push_str(&format!("    {}: {},\n", name, ty));
+    }
+    block.push_str("}\n");
+    block
+}
+
+// WGSL source with the generated declarations appended: the ShaderKind and
+// Tonemap constants and the uniform block. Appended rather than prepended so
+// error line numbers stay those of the file; WGSL globals can be used before
+// they are declared.
+pub fn wgsl_with_prelude(source: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        source,
+        ShaderKind::wgsl_constants(),
+        Tonemap::wgsl_constants(),
+        uniforms_wgsl()
+    )
+}
+
+// Diagnostic views drawn with shader_simple.wgsl instead of the planet shaders.
+// The values match its DEBUG_* constants.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugView {
+    #[default]
+    Off = 0,
+    Flat = 1,
+    Normals = 2,
+    UvChecker = 3,
+    Wireframe = 4,
+}
+
+impl DebugView {
+    pub fn next(self) -> DebugView {
+        match self {
+            DebugView::Off => DebugView::Flat,
+            DebugView::Flat => DebugView::Normals,
+            DebugView::Normals => DebugView::UvChecker,
+            DebugView::UvChecker => DebugView::Wireframe,
+            DebugView::Wireframe => DebugView::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugView::Off => "shaders de planetas",
+            DebugView::Flat => "color plano",
+            DebugView::Normals => "normales",
+            DebugView::UvChecker => "cuadrícula UV",
+            DebugView::Wireframe => "alambre",
+        }
+    }
+}
+
+// Per-object data; all objects live in one buffer, selected with a dynamic offset per draw
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ObjectUniforms {
+    model: [[f32; 4]; 4],
+    color: [f32; 3],
+    shader_type: u32,
+    params: [f32; 4], // Rings of the body or the ring itself: inner/outer radius, z = 1 if present
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    // xyz along increasing u, w the bitangent sign (see meshgen::MeshData)
+    pub tangent: [f32; 4],
+}
+
+impl Vertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+// Triangle indices in the narrowest format that fits the mesh: 16 bits while every
+// vertex can be addressed with them, 32 bits above that
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn new(indices: Vec<u32>, vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize + 1 {
+            Indices::U16(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            Indices::U32(indices)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> u32 {
+        match self {
+            Indices::U16(indices) => indices[i] as u32,
+            Indices::U32(indices) => indices[i],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::U16(_) => wgpu::IndexFormat::Uint16,
+            Indices::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Indices::U16(indices) => bytemuck::cast_slice(indices),
+            Indices::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+// Frequency of the icosphere used for every spherical body and atmosphere: 5120
+// triangles, about as many as the old 50 x 50 UV sphere but without pinched poles
+pub const SPHERE_SUBDIVISIONS: u32 = 16;
+
+pub fn create_sphere() -> (Vec<Vertex>, Indices) {
+    mesh_vertices(&meshgen::icosphere(SPHERE_SUBDIVISIONS))
+}
+
+// Interleaved vertices and indices of a generated mesh, ready for the vertex buffer
+pub fn mesh_vertices(mesh: &MeshData) -> (Vec<Vertex>, Indices) {
+    let vertices = (0..mesh.vertex_count())
+        .map(|i| Vertex {
+            position: mesh.positions[i].into(),
+            normal: mesh.normals[i].into(),
+            uv: mesh.uvs[i].into(),
+            tangent: mesh.tangents[i].into(),
+        })
+        .collect::<Vec<_>>();
+    let indices = Indices::new(mesh.indices.clone(), vertices.len());
+    (vertices, indices)
+}
+
+// Indexed mesh of an OBJ, scaled so its farthest vertex is at distance 1 like the
+// sphere: the body radius then scales both the same way. Corners without a normal
+// in the file get the average of the faces around their position.
+pub fn mesh_from_obj(obj: &Obj) -> (Vec<Vertex>, Indices) {
+    let scale = 1.0 / obj.bounding_radius().max(f32::EPSILON);
+
+    let mut smooth = vec![Vec3::zeros(); obj.positions.len()];
+    for triangle in obj.groups.iter().flat_map(|group| &group.triangles) {
+        let [a, b, c] = triangle.map(|corner| obj.positions[corner.position]);
+        // Not normalized: larger faces weigh more
+        let normal = (b - a).cross(&(c - a));
+        for corner in triangle {
+            smooth[corner.position] += normal;
+        }
+    }
+
+    let mut mesh = MeshData::default();
+    let mut seen = std::collections::HashMap::new();
+    for corner in obj.groups.iter().flat_map(|group| group.triangles.iter().flatten()) {
+        let index = *seen.entry(*corner).or_insert_with(|| {
+            let normal = match corner.normal {
+                Some(i) => obj.normals[i],
+                None => smooth[corner.position],
+            };
+            // OBJ has v = 0 at the bottom of the image; wgpu and the sphere UVs at the top
+            let uv = corner.tex_coord.map_or(Vec2::zeros(), |i| {
+                let t = obj.tex_coords[i];
+                Vec2::new(t.x, 1.0 - t.y)
+            });
+            mesh.positions.push(obj.positions[corner.position] * scale);
+            mesh.normals.push(normal.try_normalize(f32::EPSILON).unwrap_or(Vec3::y()));
+            mesh.uvs.push(uv);
+            (mesh.positions.len() - 1) as u32
+        });
+        mesh.indices.push(index);
+    }
+
+    mesh.tangents = meshgen::tangents(&mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices);
+    mesh_vertices(&mesh)
+}
+
+// One of the two instances of a ring drawn with fs_ring: 0 is the half farther than the
+// planet center, 1 the nearer one
+fn draw_ring_half<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    pipeline: &'a wgpu::RenderPipeline,
+    mesh: &'a Mesh,
+    bind_group: &'a wgpu::BindGroup,
+    offset: wgpu::DynamicOffset,
+    half: u32,
+) {
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+    render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+    render_pass.set_bind_group(1, bind_group, &[offset]);
+    render_pass.draw_indexed(0..mesh.num_indices, 0, half..half + 1);
+}
+
+#[derive(Clone, Copy)]
+enum MeshId {
+    Sphere,
+    Obj(usize),  // Index of the body with its own mesh
+    Ring(usize), // Index of the body that owns the ring
+}
+
+struct Mesh {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    // Shared by the triangle and edge buffers
+    index_format: wgpu::IndexFormat,
+    // Each triangle edge once, as a line list for the wireframe view
+    edge_buffer: wgpu::Buffer,
+    num_edges: u32,
+}
+
+fn triangle_edges(indices: &Indices, vertex_count: usize) -> Indices {
+    let mut seen = std::collections::HashSet::new();
+    let mut edges = Vec::new();
+    for t in (0..indices.len()).step_by(3) {
+        for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+            let (a, b) = (indices.get(t + a), indices.get(t + b));
+            if seen.insert((a.min(b), a.max(b))) {
+                edges.extend_from_slice(&[a, b]);
+            }
+        }
+    }
+    Indices::new(edges, vertex_count)
+}
+
+impl Mesh {
+    fn new(device: &wgpu::Device, label: &str, vertices: &[Vertex], indices: &Indices) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", label)),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Index Buffer", label)),
+            contents: indices.bytes(),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let edges = triangle_edges(indices, vertices.len());
+        let edge_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Edge Buffer", label)),
+            contents: edges.bytes(),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Mesh {
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+            index_format: indices.format(),
+            edge_buffer,
+            num_edges: edges.len() as u32,
+        }
+    }
+}
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// Depth attachment matching the target size; recreated on every resize
+fn create_depth_view(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// Pipeline for the bodies and rings from WGSL source; the generated prelude is appended here.
+// `topology` is TriangleList except for the wireframe view. Writes the two HDR
+// targets of the scene pass: lit color and emission. `transparent` selects the
+// ring variant: fs_ring, alpha blended and without depth writes.
+fn create_body_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    source: &str,
+    topology: wgpu::PrimitiveTopology,
+    transparent: bool,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(wgsl_with_prelude(source).into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: if transparent { "fs_ring" } else { "fs_main" },
+            targets: &[
+                Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(if transparent {
+                        wgpu::BlendState::ALPHA_BLENDING
+                    } else {
+                        wgpu::BlendState::REPLACE
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }),
+                Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: if transparent {
+                        wgpu::ColorWrites::empty()
+                    } else {
+                        wgpu::ColorWrites::ALL
+                    },
+                }),
+            ],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: !transparent,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub struct Renderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub width: u32,
+    pub height: u32,
+    depth_view: wgpu::TextureView,
+    // HDR targets of the scene pass, bloom and tone mapping into the output view
+    pub post: PostProcess,
+    // Kept so the body pipeline can be rebuilt when shader.wgsl changes (--watch)
+    render_pipeline_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    ring_pipeline: wgpu::RenderPipeline,
+    // shader_simple.wgsl, filled and as lines
+    debug_pipeline: wgpu::RenderPipeline,
+    wireframe_pipeline: wgpu::RenderPipeline,
+    pub debug_view: DebugView,
+    sphere: Mesh,
+    body_meshes: Vec<Option<Mesh>>, // One per body, only for bodies with an OBJ mesh
+    ring_meshes: Vec<Option<Mesh>>, // One per body, only for bodies with rings
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    uniforms: Uniforms,
+    object_buffer: wgpu::Buffer,
+    object_bind_group: wgpu::BindGroup,
+    object_stride: usize,
+    // Reused every frame: object data in draw order and the bytes uploaded for it
+    objects: Vec<(ObjectUniforms, MeshId)>,
+    object_bytes: Vec<u8>,
+    // Bodies with rings or an atmosphere and their ring slot, sorted back to front
+    transparent: Vec<(usize, Option<usize>)>,
+    starfield: Starfield,
+    atmospheres: Atmospheres,
+}
+
+impl Renderer {
+    // Renders into any texture of `format`: the window surface or an offscreen target.
+    // The scene itself is drawn in HDR and tone mapped into it at the end.
+    pub fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        scene: &Scene,
+        stars: &[Star],
+    ) -> Self {
+        let depth_view = create_depth_view(&device, width, height);
+        let post = PostProcess::new(&device, format, width, height);
+
+        let (vertices, indices) = create_sphere();
+        let sphere = Mesh::new(&device, "Sphere", &vertices, &indices);
+
+        let body_meshes = scene
+            .bodies
+            .iter()
+            .map(|body| {
+                body.mesh.as_ref().map(|obj| {
+                    let (vertices, indices) = mesh_from_obj(obj);
+                    Mesh::new(&device, &body.name, &vertices, &indices)
+                })
+            })
+            .collect();
+
+        let ring_meshes = scene
+            .bodies
+            .iter()
+            .map(|body| {
+                body.rings.map(|rings| {
+                    let (vertices, indices) = mesh_vertices(&meshgen::ring(rings.inner, rings.outer, 128));
+                    Mesh::new(&device, "Ring", &vertices, &indices)
+                })
+            })
+            .collect();
+
+        let uniforms = Uniforms {
+            view_proj: Mat4::identity().into(),
+            sky_view_proj: Mat4::identity().into(),
+            time: 0.0,
+            debug_view: DebugView::Off as u32,
+            resolution: [width as f32, height as f32],
+            light: [0.0; 4],
+            light_intensity: 0.0,
+            light_inverse_square: 0,
+            light_radius: 0.0,
+            occluder_count: 0,
+            occluders: [[0.0; 4]; MAX_OCCLUDERS],
+        };
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("uniform_bind_group_layout"),
+            });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("uniform_bind_group"),
+        });
+
+        // One slot per body and ring, each aligned for dynamic offsets
+        let object_count =
+            scene.bodies.len() + scene.bodies.iter().filter(|b| b.rings.is_some()).count();
+        let object_size = std::mem::size_of::<ObjectUniforms>();
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let object_stride = object_size.div_ceil(alignment) * alignment;
+
+        let object_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Object Uniform Buffer"),
+            size: (object_count * object_stride) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let object_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(object_size as u64),
+                    },
+                    count: None,
+                }],
+                label: Some("object_bind_group_layout"),
+            });
+
+        let object_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &object_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &object_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(object_size as u64),
+                }),
+            }],
+            label: Some("object_bind_group"),
+        });
+
+        let starfield = Starfield::new(
+            &device,
+            HDR_FORMAT,
+            DEPTH_FORMAT,
+            &uniform_bind_group_layout,
+            stars,
+        );
+
+        let atmospheres = Atmospheres::new(
+            &device,
+            HDR_FORMAT,
+            DEPTH_FORMAT,
+            &uniform_bind_group_layout,
+            Vertex::desc(),
+            &scene.bodies,
+        );
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout, &object_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let render_pipeline = create_body_pipeline(
+            &device,
+            &render_pipeline_layout,
+            include_str!("shader.wgsl"),
+            wgpu::PrimitiveTopology::TriangleList,
+            false,
+        );
+        let ring_pipeline = create_body_pipeline(
+            &device,
+            &render_pipeline_layout,
+            include_str!("shader.wgsl"),
+            wgpu::PrimitiveTopology::TriangleList,
+            true,
+        );
+        let debug_pipeline = create_body_pipeline(
+            &device,
+            &render_pipeline_layout,
+            include_str!("shader_simple.wgsl"),
+            wgpu::PrimitiveTopology::TriangleList,
+            false,
+        );
+        let wireframe_pipeline = create_body_pipeline(
+            &device,
+            &render_pipeline_layout,
+            include_str!("shader_simple.wgsl"),
+            wgpu::PrimitiveTopology::LineList,
+            false,
+        );
+
+        Self {
+            device,
+            queue,
+            width,
+            height,
+            depth_view,
+            post,
+            render_pipeline_layout,
+            render_pipeline,
+            ring_pipeline,
+            debug_pipeline,
+            wireframe_pipeline,
+            debug_view: DebugView::Off,
+            sphere,
+            body_meshes,
+            ring_meshes,
+            uniform_buffer,
+            uniform_bind_group,
+            uniforms,
+            object_buffer,
+            object_bind_group,
+            object_stride,
+            objects: Vec::with_capacity(object_count),
+            object_bytes: vec![0; object_count * object_stride],
+            transparent: Vec::new(),
+            starfield,
+            atmospheres,
+        }
+    }
+
+    // Rebuilds the body and ring pipelines from new WGSL. On a parse or validation error
+    // the previous pipelines stay in use and the error (with line numbers) is returned.
+    pub fn reload_shader(&mut self, source: &str) -> Result<(), String> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let [pipeline, ring_pipeline] = [false, true].map(|transparent| {
+            create_body_pipeline(
+                &self.device,
+                &self.render_pipeline_layout,
+                source,
+                wgpu::PrimitiveTopology::TriangleList,
+                transparent,
+            )
+        });
+        match pollster::block_on(self.device.pop_error_scope()) {
+            Some(error) => Err(error.to_string()),
+            None => {
+                self.render_pipeline = pipeline;
+                self.ring_pipeline = ring_pipeline;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.depth_view = create_depth_view(&self.device, width, height);
+        self.post.resize(&self.device, width, height);
+        self.uniforms.resolution = [width as f32, height as f32];
+    }
+
+    pub fn render(&mut self, simulation: &Simulation, view: &wgpu::TextureView) {
+        self.uniforms.time = simulation.time;
+        self.uniforms.debug_view = self.debug_view as u32;
+        let light = simulation.light();
+        self.uniforms.light = match light.source {
+            LightSource::Point(p) => [p.x, p.y, p.z, 1.0],
+            LightSource::Directional(d) => [d.x, d.y, d.z, 0.0],
+        };
+        self.uniforms.light_intensity = light.intensity;
+        self.uniforms.light_inverse_square = light.inverse_square as u32;
+        self.uniforms.light_radius = light.radius;
+        let occluders = simulation.occluders();
+        self.uniforms.occluder_count = occluders.len() as u32;
+        for (slot, occluder) in self.uniforms.occluders.iter_mut().zip(&occluders) {
+            let c = occluder.center;
+            *slot = [c.x, c.y, c.z, occluder.radius];
+        }
+        self.uniforms.view_proj = simulation.view_proj().into();
+        self.uniforms.sky_view_proj = simulation.sky_view_proj().into();
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        // Per-object data: every body mesh and the rings attached to it
+        self.objects.clear();
+        for (i, body) in simulation.scene.bodies.iter().enumerate() {
+            let nodes = &simulation.body_nodes[i];
+
+            // The body also gets its ring radii, for the ring shadow
+            let rings_params = match body.rings {
+                Some(rings) => [rings.inner, rings.outer, 1.0, 0.0],
+                None => [0.0; 4],
+            };
+            let object = ObjectUniforms {
+                model: simulation.world[nodes.mesh].into(),
+                color: body.color,
+                shader_type: body.shader.id(),
+                params: rings_params,
+            };
+            let mesh = match body.mesh {
+                Some(_) => MeshId::Obj(i),
+                None => MeshId::Sphere,
+            };
+            self.objects.push((object, mesh));
+
+            if let (Some(rings), Some(node)) = (body.rings, nodes.rings) {
+                let object = ObjectUniforms {
+                    model: simulation.world[node].into(),
+                    color: rings.color,
+                    shader_type: ShaderKind::Ring.id(),
+                    params: rings_params,
+                };
+                self.objects.push((object, MeshId::Ring(i)));
+            }
+        }
+
+        // Upload everything with a single write, each object at its aligned slot
+        for (i, (object, _)) in self.objects.iter().enumerate() {
+            let offset = i * self.object_stride;
+            let bytes = bytemuck::bytes_of(object);
+            self.object_bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        let used = self.objects.len() * self.object_stride;
+        self.queue
+            .write_buffer(&self.object_buffer, 0, &self.object_bytes[..used]);
+        self.atmospheres.update(&self.queue, simulation);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: self.post.color_view(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color {
+                                r: 0.01,
+                                g: 0.01,
+                                b: 0.03,
+                                a: 1.0,
+                            }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: self.post.emission_view(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                ],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+
+            // Whole star catalog in one instanced draw, behind everything else
+            self.starfield.draw(&mut render_pass);
+
+            // Draw opaque bodies, each with its own slot of the object buffer. The debug
+            // views draw the rings here too, as plain opaque meshes.
+            let debug = self.debug_view != DebugView::Off;
+            let wireframe = self.debug_view == DebugView::Wireframe;
+            render_pass.set_pipeline(match self.debug_view {
+                DebugView::Off => &self.render_pipeline,
+                DebugView::Wireframe => &self.wireframe_pipeline,
+                _ => &self.debug_pipeline,
+            });
+            for (i, (_, mesh)) in self.objects.iter().enumerate() {
+                let mesh = match *mesh {
+                    MeshId::Sphere => &self.sphere,
+                    MeshId::Obj(body) => self.body_meshes[body].as_ref().unwrap(),
+                    MeshId::Ring(_) if !debug => continue,
+                    MeshId::Ring(body) => self.ring_meshes[body].as_ref().unwrap(),
+                };
+                let offset = (i * self.object_stride) as wgpu::DynamicOffset;
+                let (indices, count) = if wireframe {
+                    (&mesh.edge_buffer, mesh.num_edges)
+                } else {
+                    (&mesh.index_buffer, mesh.num_indices)
+                };
+
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(indices.slice(..), mesh.index_format);
+                render_pass.set_bind_group(1, &self.object_bind_group, &[offset]);
+                render_pass.draw_indexed(0..count, 0, 0..1);
+            }
+
+            // Transparent parts, back to front by body: the far half of its rings, its
+            // atmosphere, then the near half (the two ring instances, see fs_ring)
+            if !debug {
+                self.transparent.clear();
+                self.transparent.extend(self.objects.iter().enumerate().filter_map(
+                    |(slot, (_, mesh))| match *mesh {
+                        MeshId::Ring(body) => Some((body, Some(slot))),
+                        MeshId::Sphere | MeshId::Obj(_) => None,
+                    },
+                ));
+                for body in self.atmospheres.bodies() {
+                    if !self.transparent.iter().any(|(b, _)| *b == body) {
+                        self.transparent.push((body, None));
+                    }
+                }
+                let eye = simulation.camera.eye;
+                let distance = |body: usize| {
+                    let frame = simulation.world[simulation.body_nodes[body].frame];
+                    glm::distance2(&eye, &Vec3::new(frame[(0, 3)], frame[(1, 3)], frame[(2, 3)]))
+                };
+                self.transparent
+                    .sort_by(|a, b| distance(b.0).total_cmp(&distance(a.0)));
+
+                for &(body, ring_slot) in &self.transparent {
+                    let ring = ring_slot.map(|slot| {
+                        let mesh = self.ring_meshes[body].as_ref().unwrap();
+                        (mesh, (slot * self.object_stride) as wgpu::DynamicOffset)
+                    });
+                    if let Some((mesh, offset)) = ring {
+                        draw_ring_half(&mut render_pass, &self.ring_pipeline, mesh, &self.object_bind_group, offset, 0);
+                    }
+                    self.atmospheres.draw(
+                        &mut render_pass,
+                        body,
+                        self.sphere.vertex_buffer.slice(..),
+                        self.sphere.index_buffer.slice(..),
+                        self.sphere.index_format,
+                        self.sphere.num_indices,
+                    );
+                    if let Some((mesh, offset)) = ring {
+                        draw_ring_half(&mut render_pass, &self.ring_pipeline, mesh, &self.object_bind_group, offset, 1);
+                    }
+                }
+            }
+        }
+
+        // Bloom from the emission target, then tone map into the caller's view
+        self.post.run(&self.queue, &mut encoder, view);
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniforms_wgsl_matches_the_rust_layout() {
+        // Size and alignment of each WGSL type in the uniform address space
+        let layout = |ty: &str| match ty {
+            "f32" | "u32" => (4, 4),
+            "vec2<f32>" => (8, 8),
+            "vec4<f32>" => (16, 16),
+            "mat4x4<f32>" => (64, 16),
+            "array<vec4<f32>, MAX_OCCLUDERS>" => (16 * MAX_OCCLUDERS, 16),
+            _ => panic!("unknown type {}", ty),
+        };
+        let mut offset: usize = 0;
+        let mut align = 1;
+        for (_, ty) in UNIFORM_FIELDS {
+            let (size, field_align) = layout(ty);
+            offset = offset.next_multiple_of(field_align) + size;
+            align = align.max(field_align);
+        }
+        assert_eq!(offset.next_multiple_of(align), std::mem::size_of::<Uniforms>());
+
+        let wgsl = uniforms_wgsl();
+        assert!(wgsl.contains(&format!("const MAX_OCCLUDERS: u32 = {}u;", MAX_OCCLUDERS)));
+        assert!(wgsl.contains("    occluder_count: u32,\n    occluders: array<vec4<f32>, MAX_OCCLUDERS>,\n}"));
+    }
+
+    #[test]
+    fn index_format_follows_vertex_count() {
+        // 256 x 256 vertices still fit in 16 bits, 257 x 257 no longer do
+        let (vertices, indices) = mesh_vertices(&meshgen::uv_sphere(255));
+        assert_eq!(vertices.len(), 65536);
+        assert_eq!(indices.format(), wgpu::IndexFormat::Uint16);
+
+        let (vertices, indices) = mesh_vertices(&meshgen::uv_sphere(256));
+        assert_eq!(indices.format(), wgpu::IndexFormat::Uint32);
+        assert_eq!(indices.iter().max(), Some(vertices.len() as u32 - 1));
+
+        let (vertices, indices) = mesh_vertices(&meshgen::uv_sphere(1000));
+        assert_eq!(indices.len(), 6 * 1000 * 1000);
+        assert_eq!(indices.iter().max(), Some(vertices.len() as u32 - 1));
+    }
+
+    #[test]
+    fn wireframe_edges_keep_the_format() {
+        let (vertices, indices) = mesh_vertices(&meshgen::uv_sphere(300));
+        let edges = triangle_edges(&indices, vertices.len());
+        assert_eq!(edges.format(), wgpu::IndexFormat::Uint32);
+        assert!(edges.iter().all(|i| (i as usize) < vertices.len()));
+    }
+}
@@ -0,0 +1,845 @@
+// scene.rs - Descripción de la escena cargada desde archivos TOML
+
+use crate::obj_loader::Obj;
+use crate::orbit::OrbitalElements;
+use crate::scene_graph::{NodeId, SceneGraph};
+use crate::shader_kind::ShaderKind;
+use nalgebra_glm::{self as glm, Mat4, Vec3};
+use serde::Deserialize;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use toml::Spanned;
+
+// Escena usada cuando no se pasa --scene
+pub const DEFAULT_SCENE: &str = include_str!("../scenes/default.toml");
+
+// Radios en unidades del radio del planeta
+#[derive(Debug, Clone, Copy)]
+pub struct Rings {
+    pub inner: f32,
+    pub outer: f32,
+    pub color: [f32; 3],
+}
+
+// Capa de gas alrededor de un cuerpo, con dispersión simple de Rayleigh y Mie.
+// Las longitudes están en radios del planeta y los coeficientes en 1/radio: la
+// atmósfera se exagera respecto de la real para que se vea a escala de la escena.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    // Grosor de la capa sobre la superficie
+    pub height: f32,
+    // Por canal RGB; el azul se dispersa más que el rojo
+    pub rayleigh: [f32; 3],
+    pub rayleigh_scale_height: f32,
+    // Partículas grandes (polvo, neblina): igual en todos los canales
+    pub mie: f32,
+    pub mie_scale_height: f32,
+    // Anisotropía de Henyey-Greenstein: cuánto se concentra hacia adelante
+    pub mie_g: f32,
+    // Multiplica la luz del sol que recibe la atmósfera
+    pub intensity: f32,
+}
+
+impl Atmosphere {
+    pub const EARTH: Atmosphere = Atmosphere {
+        height: 0.12,
+        rayleigh: [1.6, 3.8, 9.3],
+        rayleigh_scale_height: 0.025,
+        mie: 2.0,
+        mie_scale_height: 0.012,
+        mie_g: 0.76,
+        intensity: 8.0,
+    };
+
+    // Delgada y con mucho polvo rojizo, que domina sobre Rayleigh
+    pub const MARS: Atmosphere = Atmosphere {
+        height: 0.08,
+        rayleigh: [0.9, 0.5, 0.3],
+        rayleigh_scale_height: 0.02,
+        mie: 3.0,
+        mie_scale_height: 0.015,
+        mie_g: 0.65,
+        intensity: 8.0,
+    };
+
+    // Envoltura espesa de neblina, con un borde ancho y poco azul
+    pub const GAS_GIANT: Atmosphere = Atmosphere {
+        height: 0.1,
+        rayleigh: [1.2, 1.6, 2.4],
+        rayleigh_scale_height: 0.035,
+        mie: 1.5,
+        mie_scale_height: 0.03,
+        mie_g: 0.7,
+        intensity: 10.0,
+    };
+
+    pub fn preset(name: &str) -> Option<Atmosphere> {
+        match name {
+            "earth" => Some(Atmosphere::EARTH),
+            "mars" => Some(Atmosphere::MARS),
+            "gas_giant" => Some(Atmosphere::GAS_GIANT),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub name: String,
+    pub shader: ShaderKind,
+    pub radius: f32,
+    pub position: Vec3,
+    pub orbit: Option<OrbitalElements>,
+    pub parent: Option<usize>,
+    pub color: [f32; 3],
+    // Inclinación del eje en radianes; también inclina las órbitas de sus hijos
+    pub tilt: f32,
+    pub rotation_period: Option<f32>,
+    pub rings: Option<Rings>,
+    pub atmosphere: Option<Atmosphere>,
+    // Malla OBJ en lugar de la esfera, ya escalada a radio 1 por los renderers
+    pub mesh: Option<Arc<Obj>>,
+}
+
+// Nodos del grafo de escena que corresponden a un cuerpo
+#[derive(Debug, Clone, Copy)]
+pub struct BodyNodes {
+    // Marco orbital: traslación + inclinación del eje, heredado por los hijos
+    pub frame: NodeId,
+    // Malla del cuerpo: giro propio + escala, no se hereda
+    pub mesh: NodeId,
+    pub rings: Option<NodeId>,
+}
+
+// Vista inicial: la cámara orbita alrededor del origen (ángulos en radianes)
+#[derive(Debug, Clone, Copy)]
+pub struct CameraSettings {
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov_y: f32,
+}
+
+// El primer cuerpo `sun` es una luz puntual; sin sol, una luz direccional fija
+#[derive(Debug, Clone, Copy)]
+pub struct LightSettings {
+    // Irradiancia a distancia 1 del sol con atenuación, o en todas partes sin ella
+    pub intensity: f32,
+    pub inverse_square: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub bodies: Vec<Body>,
+    pub camera: CameraSettings,
+    pub light: LightSettings,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    Invalid {
+        origin: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => write!(f, "{}: {}", path, source),
+            SceneError::Invalid {
+                origin,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", origin, line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+// Formato en disco: [[body]] por cada cuerpo celeste
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(rename = "body", default)]
+    bodies: Vec<BodyDef>,
+    camera: Option<Spanned<CameraDef>>,
+    light: Option<Spanned<LightDef>>,
+}
+
+// Ángulos en grados
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDef {
+    #[serde(default = "default_distance")]
+    distance: f32,
+    #[serde(default)]
+    yaw: f32,
+    #[serde(default)]
+    pitch: f32,
+    #[serde(default = "default_fov")]
+    fov: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDef {
+    #[serde(default = "default_intensity")]
+    intensity: f32,
+    // "none" o "inverse_square"
+    falloff: Option<Spanned<String>>,
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_distance() -> f32 {
+    3.0
+}
+
+fn default_fov() -> f32 {
+    45.0
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BodyDef {
+    name: Spanned<String>,
+    shader: Spanned<String>,
+    radius: Spanned<f32>,
+    #[serde(default)]
+    position: [f32; 3],
+    orbit: Option<Spanned<OrbitDef>>,
+    parent: Option<Spanned<String>>,
+    color: Option<Spanned<[f32; 3]>>,
+    // En grados
+    tilt: Option<Spanned<f32>>,
+    rotation_period: Option<Spanned<f32>>,
+    rings: Option<Spanned<RingsDef>>,
+    atmosphere: Option<Spanned<AtmosphereDef>>,
+    // Ruta relativa al archivo de escena
+    mesh: Option<Spanned<String>>,
+}
+
+// Parte de un preset ("earth" si no se indica) y reemplaza los campos presentes
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtmosphereDef {
+    preset: Option<Spanned<String>>,
+    height: Option<f32>,
+    rayleigh: Option<[f32; 3]>,
+    rayleigh_scale_height: Option<f32>,
+    mie: Option<f32>,
+    mie_scale_height: Option<f32>,
+    mie_g: Option<f32>,
+    intensity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RingsDef {
+    inner: f32,
+    outer: f32,
+    #[serde(default = "white")]
+    color: [f32; 3],
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitDef {
+    semi_major_axis: f32,
+    #[serde(default)]
+    eccentricity: f32,
+    // Ángulos en grados
+    #[serde(default)]
+    inclination: f32,
+    #[serde(default)]
+    ascending_node: f32,
+    #[serde(default)]
+    argument_of_periapsis: f32,
+    period: f32,
+    #[serde(default)]
+    phase: f32,
+}
+
+// Convierte un offset en bytes a (línea, columna), ambos desde 1
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Scene, SceneError> {
+        let source = std::fs::read_to_string(path).map_err(|source| SceneError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Scene::parse(&source, &path.display().to_string())
+    }
+
+    // `origin` identifica el archivo en los mensajes de error; las rutas de las
+    // mallas son relativas a su directorio
+    pub fn parse(source: &str, origin: &str) -> Result<Scene, SceneError> {
+        let error = |span: Range<usize>, message: String| {
+            let (line, column) = line_column(source, span.start);
+            SceneError::Invalid {
+                origin: origin.to_string(),
+                line,
+                column,
+                message,
+            }
+        };
+
+        let file: SceneFile = toml::from_str(source)
+            .map_err(|e| error(e.span().unwrap_or(0..0), e.message().to_string()))?;
+
+        if file.bodies.is_empty() {
+            return Err(error(0..0, "la escena no declara ningún [[body]]".to_string()));
+        }
+
+        let mut bodies: Vec<Body> = Vec::with_capacity(file.bodies.len());
+        for def in file.bodies {
+            let name = def.name.get_ref();
+            if bodies.iter().any(|b| &b.name == name) {
+                return Err(error(def.name.span(), format!("cuerpo '{}' duplicado", name)));
+            }
+
+            let shader = def
+                .shader
+                .get_ref()
+                .parse::<ShaderKind>()
+                .map_err(|e| error(def.shader.span(), e))?;
+
+            let radius = *def.radius.get_ref();
+            // NaN e inf son válidos en TOML y pasarían las comparaciones
+            if !radius.is_finite() || radius <= 0.0 {
+                return Err(error(def.radius.span(), "radius debe ser mayor que 0".to_string()));
+            }
+
+            let orbit = match def.orbit {
+                Some(orbit) => {
+                    let span = orbit.span();
+                    let orbit = orbit.into_inner();
+                    // NaN e inf son válidos en TOML y pasarían las comparaciones
+                    let elements = [
+                        orbit.semi_major_axis,
+                        orbit.eccentricity,
+                        orbit.inclination,
+                        orbit.ascending_node,
+                        orbit.argument_of_periapsis,
+                        orbit.period,
+                        orbit.phase,
+                    ];
+                    if elements.iter().any(|x| !x.is_finite()) {
+                        return Err(error(span, "los elementos de orbit deben ser números finitos".to_string()));
+                    }
+                    if orbit.semi_major_axis < 0.0 || orbit.period <= 0.0 {
+                        return Err(error(
+                            span,
+                            "orbit requiere semi_major_axis >= 0 y period > 0".to_string(),
+                        ));
+                    }
+                    if !(0.0..1.0).contains(&orbit.eccentricity) {
+                        return Err(error(
+                            span,
+                            "eccentricity debe estar en [0, 1) para una órbita cerrada".to_string(),
+                        ));
+                    }
+                    Some(OrbitalElements {
+                        semi_major_axis: orbit.semi_major_axis,
+                        eccentricity: orbit.eccentricity,
+                        inclination: orbit.inclination.to_radians(),
+                        ascending_node: orbit.ascending_node.to_radians(),
+                        argument_of_periapsis: orbit.argument_of_periapsis.to_radians(),
+                        period: orbit.period,
+                        phase: orbit.phase.to_radians(),
+                    })
+                }
+                None => None,
+            };
+
+            // El padre debe estar declarado antes, así no puede haber ciclos
+            let parent = match &def.parent {
+                Some(parent) => Some(
+                    bodies
+                        .iter()
+                        .position(|b| &b.name == parent.get_ref())
+                        .ok_or_else(|| {
+                            error(
+                                parent.span(),
+                                format!(
+                                    "el padre '{}' no existe o se declara después de '{}'",
+                                    parent.get_ref(),
+                                    name
+                                ),
+                            )
+                        })?,
+                ),
+                None => None,
+            };
+
+            let color = match def.color {
+                Some(color) => {
+                    if color.get_ref().iter().any(|c| !c.is_finite() || *c < 0.0) {
+                        return Err(error(
+                            color.span(),
+                            "los componentes de color no pueden ser negativos".to_string(),
+                        ));
+                    }
+                    color.into_inner()
+                }
+                None => white(),
+            };
+
+            let rotation_period = match def.rotation_period {
+                Some(period) => {
+                    if !period.get_ref().is_finite() || *period.get_ref() == 0.0 {
+                        return Err(error(
+                            period.span(),
+                            "rotation_period debe ser finito y distinto de 0 (omitirlo para no girar)".to_string(),
+                        ));
+                    }
+                    Some(period.into_inner())
+                }
+                None => None,
+            };
+
+            let rings = match def.rings {
+                Some(rings) => {
+                    let span = rings.span();
+                    let rings = rings.into_inner();
+                    let values = [rings.inner, rings.outer];
+                    if !values.iter().chain(&rings.color).all(|x| x.is_finite()) {
+                        return Err(error(span, "los valores de rings deben ser números finitos".to_string()));
+                    }
+                    if rings.inner < 1.0 || rings.outer <= rings.inner {
+                        return Err(error(
+                            span,
+                            "rings requiere 1 <= inner < outer (en radios del planeta)".to_string(),
+                        ));
+                    }
+                    Some(Rings {
+                        inner: rings.inner,
+                        outer: rings.outer,
+                        color: rings.color,
+                    })
+                }
+                None => None,
+            };
+
+            let tilt = match def.tilt {
+                Some(tilt) => {
+                    if !tilt.get_ref().is_finite() {
+                        return Err(error(tilt.span(), "tilt debe ser un número finito".to_string()));
+                    }
+                    tilt.into_inner()
+                }
+                None => 0.0,
+            };
+
+            let atmosphere = match def.atmosphere {
+                Some(atmosphere) => {
+                    let span = atmosphere.span();
+                    let def = atmosphere.into_inner();
+                    let mut atmosphere = match &def.preset {
+                        Some(preset) => Atmosphere::preset(preset.get_ref()).ok_or_else(|| {
+                            error(
+                                preset.span(),
+                                format!(
+                                    "preset de atmósfera desconocido '{}' (esperado: earth, mars, gas_giant)",
+                                    preset.get_ref()
+                                ),
+                            )
+                        })?,
+                        None => Atmosphere::EARTH,
+                    };
+                    atmosphere.height = def.height.unwrap_or(atmosphere.height);
+                    atmosphere.rayleigh = def.rayleigh.unwrap_or(atmosphere.rayleigh);
+                    atmosphere.rayleigh_scale_height =
+                        def.rayleigh_scale_height.unwrap_or(atmosphere.rayleigh_scale_height);
+                    atmosphere.mie = def.mie.unwrap_or(atmosphere.mie);
+                    atmosphere.mie_scale_height = def.mie_scale_height.unwrap_or(atmosphere.mie_scale_height);
+                    atmosphere.mie_g = def.mie_g.unwrap_or(atmosphere.mie_g);
+                    atmosphere.intensity = def.intensity.unwrap_or(atmosphere.intensity);
+
+                    // Las comparaciones ya rechazan NaN, pero no inf
+                    let finite = [
+                        atmosphere.height,
+                        atmosphere.rayleigh_scale_height,
+                        atmosphere.mie,
+                        atmosphere.mie_scale_height,
+                        atmosphere.intensity,
+                    ]
+                    .iter()
+                    .chain(&atmosphere.rayleigh)
+                    .all(|x| x.is_finite());
+                    let valid = finite
+                        && atmosphere.height > 0.0
+                        && atmosphere.rayleigh_scale_height > 0.0
+                        && atmosphere.mie_scale_height > 0.0
+                        && atmosphere.rayleigh.iter().all(|c| *c >= 0.0)
+                        && atmosphere.mie >= 0.0
+                        && atmosphere.intensity >= 0.0;
+                    if !valid {
+                        return Err(error(
+                            span,
+                            "atmosphere requiere height y alturas de escala > 0, y coeficientes e intensidad >= 0"
+                                .to_string(),
+                        ));
+                    }
+                    if !(atmosphere.mie_g > -1.0 && atmosphere.mie_g < 1.0) {
+                        return Err(error(span, "atmosphere.mie_g debe estar en (-1, 1)".to_string()));
+                    }
+                    Some(atmosphere)
+                }
+                None => None,
+            };
+
+            let mesh = match def.mesh {
+                Some(mesh) => {
+                    let directory = Path::new(origin).parent().unwrap_or(Path::new(""));
+                    let obj = Obj::load(&directory.join(mesh.get_ref()))
+                        .map_err(|e| error(mesh.span(), format!("malla inválida: {}", e)))?;
+                    if obj.triangle_count() == 0 {
+                        return Err(error(mesh.span(), format!("la malla {} no tiene caras", mesh.get_ref())));
+                    }
+                    Some(Arc::new(obj))
+                }
+                None => None,
+            };
+
+            bodies.push(Body {
+                name: def.name.into_inner(),
+                shader,
+                radius,
+                position: Vec3::from(def.position),
+                orbit,
+                parent,
+                color,
+                tilt: tilt.to_radians(),
+                rotation_period,
+                rings,
+                atmosphere,
+                mesh,
+            });
+        }
+
+        let camera = match file.camera {
+            Some(camera) => {
+                let span = camera.span();
+                let camera = camera.into_inner();
+                let values = [camera.distance, camera.yaw, camera.pitch, camera.fov];
+                if values.iter().any(|x| !x.is_finite()) {
+                    return Err(error(span, "los valores de camera deben ser números finitos".to_string()));
+                }
+                if camera.distance <= 0.0 || !(1.0..179.0).contains(&camera.fov) {
+                    return Err(error(
+                        span,
+                        "camera requiere distance > 0 y fov entre 1 y 179 grados".to_string(),
+                    ));
+                }
+                if camera.pitch.abs() >= 90.0 {
+                    return Err(error(span, "camera.pitch debe estar entre -90 y 90 grados".to_string()));
+                }
+                camera
+            }
+            None => CameraDef {
+                distance: default_distance(),
+                yaw: 0.0,
+                pitch: 0.0,
+                fov: default_fov(),
+            },
+        };
+
+        let light = match file.light {
+            Some(light) => {
+                let span = light.span();
+                let light = light.into_inner();
+                if !light.intensity.is_finite() || light.intensity < 0.0 {
+                    return Err(error(span, "light.intensity debe ser finita y no negativa".to_string()));
+                }
+                let inverse_square = match &light.falloff {
+                    None => false,
+                    Some(falloff) => match falloff.get_ref().as_str() {
+                        "none" => false,
+                        "inverse_square" => true,
+                        other => {
+                            return Err(error(
+                                falloff.span(),
+                                format!(
+                                    "falloff desconocido '{}' (esperado: none, inverse_square)",
+                                    other
+                                ),
+                            ));
+                        }
+                    },
+                };
+                LightSettings {
+                    intensity: light.intensity,
+                    inverse_square,
+                }
+            }
+            None => LightSettings {
+                intensity: default_intensity(),
+                inverse_square: false,
+            },
+        };
+
+        Ok(Scene {
+            bodies,
+            light,
+            camera: CameraSettings {
+                distance: camera.distance,
+                yaw: camera.yaw.to_radians(),
+                pitch: camera.pitch.to_radians(),
+                fov_y: camera.fov.to_radians(),
+            },
+        })
+    }
+
+    // Crea los nodos de cada cuerpo; las transformaciones se llenan en `update_graph`
+    pub fn build_graph(&self) -> (SceneGraph, Vec<BodyNodes>) {
+        let mut graph = SceneGraph::new();
+        let mut nodes: Vec<BodyNodes> = Vec::with_capacity(self.bodies.len());
+
+        for body in &self.bodies {
+            let parent = body.parent.map(|p| nodes[p].frame);
+            let frame = graph.add(parent, Mat4::identity());
+            let mesh = graph.add(Some(frame), Mat4::identity());
+            let rings = body.rings.map(|_| graph.add(Some(frame), Mat4::identity()));
+            nodes.push(BodyNodes { frame, mesh, rings });
+        }
+
+        (graph, nodes)
+    }
+
+    pub fn update_graph(&self, graph: &mut SceneGraph, nodes: &[BodyNodes], time: f32) {
+        for (body, nodes) in self.bodies.iter().zip(nodes) {
+            let mut offset = body.position;
+            if let Some(orbit) = &body.orbit {
+                offset += orbit.position(time);
+            }
+            let frame = glm::translation(&offset) * glm::rotation(body.tilt, &Vec3::z());
+            graph.set_local(nodes.frame, frame);
+
+            let spin = match body.rotation_period {
+                Some(period) => time * std::f32::consts::TAU / period,
+                None => 0.0,
+            };
+            let scale = glm::scaling(&Vec3::from_element(body.radius));
+            graph.set_local(nodes.mesh, glm::rotation(spin, &Vec3::y()) * scale);
+
+            if let Some(rings) = nodes.rings {
+                graph.set_local(rings, scale);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_atmosphere(table: &str) -> Result<Option<Atmosphere>, SceneError> {
+        let source = format!(
+            "[[body]]\nname = \"a\"\nshader = \"rocky\"\nradius = 1.0\n{}",
+            table
+        );
+        Scene::parse(&source, "test.toml").map(|scene| scene.bodies[0].atmosphere)
+    }
+
+    // Línea, columna y mensaje del error de una escena inválida
+    fn invalid(source: &str) -> (usize, usize, String) {
+        match Scene::parse(source, "test.toml") {
+            Err(SceneError::Invalid {
+                origin,
+                line,
+                column,
+                message,
+            }) => {
+                assert_eq!(origin, "test.toml");
+                (line, column, message)
+            }
+            other => panic!("se esperaba un error de escena: {:?}", other.map(|s| s.bodies.len())),
+        }
+    }
+
+    const SUN: &str = "[[body]]\nname = \"sol\"\nshader = \"sun\"\nradius = 1.0\n";
+
+    #[test]
+    fn duplicate_names_point_at_the_second_name() {
+        let source = format!("{}\n[[body]]\nname = \"sol\"\nshader = \"moon\"\nradius = 0.5\n", SUN);
+        assert_eq!(invalid(&source), (7, 8, "cuerpo 'sol' duplicado".to_string()));
+    }
+
+    #[test]
+    fn unknown_shaders_list_the_valid_names() {
+        let source = "[[body]]\nname = \"a\"\nshader = \"plasma\"\nradius = 1.0\n";
+        let (line, column, message) = invalid(source);
+        assert_eq!((line, column), (3, 10));
+        assert!(message.starts_with("shader desconocido 'plasma' (esperado: "), "{}", message);
+        assert!(message.contains("rocky") && message.contains("moon"), "{}", message);
+    }
+
+    #[test]
+    fn parents_must_be_declared_before() {
+        let child = "[[body]]\nname = \"luna\"\nshader = \"moon\"\nradius = 0.5\nparent = \"tierra\"\n";
+        let message = "el padre 'tierra' no existe o se declara después de 'luna'".to_string();
+        assert_eq!(invalid(child), (5, 10, message.clone()));
+
+        // Declarado más abajo tampoco sirve
+        let later = format!("{}\n[[body]]\nname = \"tierra\"\nshader = \"rocky\"\nradius = 1.0\n", child);
+        assert_eq!(invalid(&later), (5, 10, message));
+    }
+
+    #[test]
+    fn toml_errors_keep_their_position() {
+        // El valor mal tipado: toml informa el span de `radius` en la segunda línea del cuerpo
+        let (line, column, message) = invalid("[[body]]\nname = \"a\"\nshader = \"rocky\"\nradius = \"grande\"\n");
+        assert_eq!((line, column), (4, 10));
+        assert!(message.contains("f32") || message.contains("float"), "{}", message);
+
+        let (line, _, message) = invalid("[[body]]\nname = \"a\"\n");
+        assert_eq!(line, 1);
+        assert!(message.contains("shader"), "{}", message);
+
+        let (line, column, message) = invalid("[camera]\ndistance = 3.0\n");
+        assert_eq!((line, column, message.as_str()), (1, 1, "la escena no declara ningún [[body]]"));
+    }
+
+    #[test]
+    fn non_finite_numbers_are_rejected() {
+        // TOML acepta nan e inf como flotantes válidos
+        let radius = "radius debe ser mayor que 0";
+        let color = "los componentes de color no pueden ser negativos";
+        let rotation_period = "rotation_period debe ser finito y distinto de 0 (omitirlo para no girar)";
+        let rings = "los valores de rings deben ser números finitos";
+        let cases = [
+            ("radius = inf", (4, 10, radius)),
+            ("radius = nan", (4, 10, radius)),
+            ("radius = 1.0\ncolor = [1.0, nan, 1.0]", (5, 9, color)),
+            ("radius = 1.0\nrotation_period = nan", (5, 19, rotation_period)),
+            ("radius = 1.0\nrotation_period = -inf", (5, 19, rotation_period)),
+            ("radius = 1.0\ntilt = inf", (5, 8, "tilt debe ser un número finito")),
+            ("radius = 1.0\nrings = { inner = 1.5, outer = inf }", (5, 9, rings)),
+            ("radius = 1.0\nrings = { inner = nan, outer = 2.0 }", (5, 9, rings)),
+            ("radius = 1.0\nrings = { inner = 1.5, outer = 2.0, color = [inf, 1.0, 1.0] }", (5, 9, rings)),
+        ];
+        for (fields, (line, column, message)) in cases {
+            let source = format!("[[body]]\nname = \"a\"\nshader = \"rocky\"\n{}\n", fields);
+            assert_eq!(invalid(&source), (line, column, message.to_string()), "{}", fields);
+        }
+    }
+
+    #[test]
+    fn orbits_reject_nan_and_infinite_elements() {
+        let finite = "los elementos de orbit deben ser números finitos";
+        let range = "orbit requiere semi_major_axis >= 0 y period > 0";
+        for (orbit, message) in [
+            ("semi_major_axis = nan, period = 1.0", finite),
+            ("semi_major_axis = inf, period = 1.0", finite),
+            ("semi_major_axis = 1.0, period = nan", finite),
+            ("semi_major_axis = 1.0, period = 1.0, eccentricity = nan", finite),
+            ("semi_major_axis = 1.0, period = 1.0, inclination = inf", finite),
+            ("semi_major_axis = 1.0, period = 1.0, ascending_node = nan", finite),
+            ("semi_major_axis = 1.0, period = 1.0, argument_of_periapsis = -inf", finite),
+            ("semi_major_axis = 1.0, period = 1.0, phase = nan", finite),
+            ("semi_major_axis = -1.0, period = 1.0", range),
+            ("semi_major_axis = 1.0, period = 0.0", range),
+        ] {
+            let source = format!("{}orbit = {{ {} }}\n", SUN, orbit);
+            assert_eq!(invalid(&source), (5, 9, message.to_string()), "{}", orbit);
+        }
+    }
+
+    #[test]
+    fn camera_rejects_non_finite_values() {
+        for camera in ["distance = nan", "distance = inf", "yaw = inf", "pitch = nan", "fov = nan"] {
+            let source = format!("{}\n[camera]\n{}\n", SUN, camera);
+            let message = "los valores de camera deben ser números finitos".to_string();
+            assert_eq!(invalid(&source), (6, 1, message), "{}", camera);
+        }
+    }
+
+    #[test]
+    fn light_intensity_must_be_finite() {
+        for intensity in ["nan", "inf", "-1.0"] {
+            let source = format!("{}\n[light]\nintensity = {}\n", SUN, intensity);
+            let message = "light.intensity debe ser finita y no negativa".to_string();
+            assert_eq!(invalid(&source), (6, 1, message), "{}", intensity);
+        }
+    }
+
+    #[test]
+    fn atmosphere_fields_override_the_preset() {
+        assert_eq!(parse_atmosphere("").unwrap(), None);
+        assert_eq!(parse_atmosphere("atmosphere = {}").unwrap(), Some(Atmosphere::EARTH));
+
+        let mars = parse_atmosphere("atmosphere = { preset = \"mars\", mie_g = 0.5 }").unwrap();
+        assert_eq!(
+            mars,
+            Some(Atmosphere {
+                mie_g: 0.5,
+                ..Atmosphere::MARS
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_atmospheres_are_rejected() {
+        for table in [
+            "atmosphere = { preset = \"venus\" }",
+            "atmosphere = { height = 0.0 }",
+            "atmosphere = { rayleigh = [1.0, -1.0, 1.0] }",
+            "atmosphere = { mie_g = 1.0 }",
+            "atmosphere = { height = inf }",
+            "atmosphere = { rayleigh = [1.0, nan, 1.0] }",
+            "atmosphere = { mie = inf }",
+            "atmosphere = { intensity = inf }",
+            "atmosphere = { mie_g = nan }",
+        ] {
+            let error = parse_atmosphere(table).unwrap_err().to_string();
+            assert!(error.contains("test.toml:5"), "{}: {}", table, error);
+        }
+    }
+
+    #[test]
+    fn meshes_load_relative_to_the_scene() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        let origin = directory.join("test.toml").display().to_string();
+        let body = |mesh: &str| {
+            format!("[[body]]\nname = \"a\"\nshader = \"moon\"\nradius = 1.0\nmesh = \"{}\"\n", mesh)
+        };
+
+        let scene = Scene::parse(&body("../assets/asteroide.obj"), &origin).unwrap();
+        assert!(scene.bodies[0].mesh.as_ref().is_some_and(|obj| obj.triangle_count() > 0));
+
+        // El error de la malla apunta a la línea de `mesh` en la escena
+        let error = Scene::parse(&body("no_existe.obj"), &origin).unwrap_err().to_string();
+        assert!(error.contains("test.toml:5") && error.contains("no_existe.obj"), "{}", error);
+    }
+}
@@ -0,0 +1,96 @@
+// scene_graph.rs - Jerarquía de transformaciones padre/hijo
+//
+// Cada nodo guarda su transformación local; la de mundo se compone cada cuadro
+// como world(padre) * local. Los padres siempre se agregan antes que sus hijos.
+
+use nalgebra_glm::Mat4;
+
+pub type NodeId = usize;
+
+struct Node {
+    parent: Option<NodeId>,
+    local: Mat4,
+}
+
+#[derive(Default)]
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        SceneGraph::default()
+    }
+
+    pub fn add(&mut self, parent: Option<NodeId>, local: Mat4) -> NodeId {
+        if let Some(parent) = parent {
+            assert!(parent < self.nodes.len(), "el padre debe existir antes que el hijo");
+        }
+        self.nodes.push(Node { parent, local });
+        self.nodes.len() - 1
+    }
+
+    pub fn set_local(&mut self, node: NodeId, local: Mat4) {
+        self.nodes[node].local = local;
+    }
+
+    pub fn world_matrices(&self) -> Vec<Mat4> {
+        let mut world: Vec<Mat4> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let matrix = match node.parent {
+                Some(parent) => world[parent] * node.local,
+                None => node.local,
+            };
+            world.push(matrix);
+        }
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{self as glm, Vec3};
+
+    fn assert_matrix_eq(a: &Mat4, b: &Mat4) {
+        assert!((a - b).abs().max() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn world_is_parent_times_local_down_the_tree() {
+        let mut graph = SceneGraph::new();
+        let sun = glm::translate(&Mat4::identity(), &Vec3::new(1.0, 0.0, 0.0));
+        let planet = glm::rotate_y(&glm::translate(&Mat4::identity(), &Vec3::new(0.0, 0.0, 5.0)), 0.7);
+        let moon = glm::scale(&glm::translate(&Mat4::identity(), &Vec3::new(2.0, 0.0, 0.0)), &Vec3::repeat(0.5));
+
+        // Dos árboles intercalados: el orden de inserción no importa mientras cada
+        // padre exista antes que sus hijos
+        let a = graph.add(None, sun);
+        let other = graph.add(None, planet);
+        let b = graph.add(Some(a), planet);
+        let other_child = graph.add(Some(other), moon);
+        let c = graph.add(Some(b), moon);
+
+        let world = graph.world_matrices();
+        assert_matrix_eq(&world[a], &sun);
+        assert_matrix_eq(&world[b], &(world[a] * planet));
+        assert_matrix_eq(&world[c], &(world[b] * moon));
+        assert_matrix_eq(&world[c], &(sun * planet * moon));
+        assert_matrix_eq(&world[other_child], &(planet * moon));
+
+        // Cambiar la local del padre mueve a toda su descendencia y a nadie más
+        let moved = glm::translate(&Mat4::identity(), &Vec3::new(0.0, 3.0, 0.0));
+        graph.set_local(a, moved);
+        let world = graph.world_matrices();
+        assert_matrix_eq(&world[c], &(moved * planet * moon));
+        assert_matrix_eq(&world[other_child], &(planet * moon));
+    }
+
+    #[test]
+    #[should_panic(expected = "el padre debe existir antes que el hijo")]
+    fn parents_come_before_children() {
+        let mut graph = SceneGraph::new();
+        graph.add(None, Mat4::identity());
+        graph.add(Some(1), Mat4::identity());
+    }
+}
@@ -1,17 +1,28 @@
 // shader.wgsl - Shaders de planetas procedurales en GPU
+//
+// Las constantes SHADER_*, MAX_OCCLUDERS y `struct Uniforms` no están en este
+// archivo: las generan ShaderKind (shader_kind.rs) y renderer::uniforms_wgsl a
+// partir de los tipos de Rust, y se agregan al final del código al crear el
+// pipeline. Uniforms lleva la cámara, el tiempo, la luz (light.xyz: posición del
+// sol con w = 1 o dirección hacia la luz con w = 0; light_radius para la
+// penumbra) y los oclusores (xyz centro, w radio).
 
-struct Uniforms {
-    time: f32,
+const PI: f32 = 3.14159265;
+
+// Datos de cada objeto, elegidos con un offset dinámico por draw
+struct Object {
+    model: mat4x4<f32>,
+    color: vec3<f32>,
     shader_type: u32,
-    resolution: vec2<f32>,
-    planet_position: vec2<f32>,
-    planet_scale: f32,
-    _padding: f32,
+    params: vec4<f32>, // Anillos del cuerpo o del propio anillo: x, y radio interior/exterior; z = 1 si hay
 }
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+@group(1) @binding(0)
+var<uniform> object: Object;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
@@ -21,33 +32,33 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) world_pos: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) local_pos: vec3<f32>,
+    // Sólo para fs_ring: profundidad de vista del punto y del centro del cuerpo,
+    // y qué mitad del anillo se dibuja (0 la lejana, 1 la cercana)
+    @location(3) view_depth: f32,
+    @location(4) @interpolate(flat) center_depth: f32,
+    @location(5) @interpolate(flat) ring_half: u32,
 }
 
 @vertex
-fn vs_main(input: VertexInput) -> VertexOutput {
+fn vs_main(input: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
     var output: VertexOutput;
     
-    // Aplicar rotación
-    let angle = uniforms.time * 0.3;
-    let cos_a = cos(angle);
-    let sin_a = sin(angle);
+    // Matriz de mundo compuesta por el grafo de escena (órbita, inclinación, giro y escala)
+    let world_pos = object.model * vec4<f32>(input.position, 1.0);
+    let world_normal = object.model * vec4<f32>(input.normal, 0.0);
     
-    let rot_y = mat3x3<f32>(
-        vec3<f32>(cos_a, 0.0, sin_a),
-        vec3<f32>(0.0, 1.0, 0.0),
-        vec3<f32>(-sin_a, 0.0, cos_a)
-    );
+    // Vista look-at y proyección en perspectiva
+    output.clip_position = uniforms.view_proj * world_pos;
+    output.world_pos = world_pos.xyz;
+    output.normal = normalize(world_normal.xyz);
     
-    // Escalar y rotar
-    let scaled_pos = input.position * uniforms.planet_scale;
-    let rotated_pos = rot_y * scaled_pos;
-    let rotated_normal = rot_y * input.normal;
+    // Los patrones procedurales se evalúan en espacio objeto para que giren con el cuerpo
+    output.local_pos = input.position;
     
-    // Proyección simple con offset de posición
-    let pos = rotated_pos * vec3<f32>(1.0, 1.0, 0.5);
-    output.clip_position = vec4<f32>(pos.xy + uniforms.planet_position, 0.5, 1.0);
-    output.world_pos = rotated_pos;
-    output.normal = normalize(rotated_normal);
+    output.view_depth = output.clip_position.w;
+    output.center_depth = (uniforms.view_proj * object.model * vec4<f32>(0.0, 0.0, 0.0, 1.0)).w;
+    output.ring_half = instance;
     
     return output;
 }
@@ -138,7 +149,8 @@ fn sun_shader(pos: vec3<f32>, time: f32) -> vec3<f32> {
     
     // Corona pulsante
     let dist = length(p);
-    let corona = pow(1.0 - dist * 0.4, 3.0) * 1.5;
+    // pow con base negativa es NaN (el sol salía negro)
+    let corona = pow(max(1.0 - dist * 0.4, 0.0), 3.0) * 1.5;
     let pulse = sin(time * 2.0) * 0.2 + 1.2;
     
     // Gradiente de temperatura
@@ -272,54 +284,12 @@ fn ringed_planet_shader(pos: vec3<f32>, time: f32) -> vec3<f32> {
     let bands = sin(p.y * 20.0 + fbm(p, 2) * 0.5) * 0.5 + 0.5;
     let color1 = vec3<f32>(0.2, 0.5, 0.3); // Verde oscuro
     let color2 = vec3<f32>(0.3, 0.7, 0.4); // Verde medio
-    var planet_color = mix(color1, color2, bands);
-
-    // ANILLOS ESPECTACULARES
-    let ring_dist = length(p.xz);
-    let y_abs = abs(p.y);
-    
-    if (y_abs < 0.18 && ring_dist > 0.75 && ring_dist < 2.0) {
-        let ring_freq = ring_dist * 50.0;
-        let ring_bands = sin(ring_freq) * 0.5 + 0.5;
-        let brightness_var = sin(ring_dist * 30.0 + time * 3.0) * 0.5 + 0.5;
-        
-        // Gaps de Cassini
-        let is_gap = (ring_dist > 1.0 && ring_dist < 1.15) ||
-                     (ring_dist > 1.5 && ring_dist < 1.55) ||
-                     (ring_dist > 1.75 && ring_dist < 1.78);
-        
-        if (is_gap) {
-            planet_color *= 0.4;
-        } else {
-            var ring_color: vec3<f32>;
-            if (ring_bands > 0.7) {
-                ring_color = vec3<f32>(0.3, 0.8, 0.5); // Verde brillante
-            } else if (ring_bands > 0.4) {
-                ring_color = vec3<f32>(0.2, 0.6, 0.4); // Verde medio
-            } else {
-                ring_color = vec3<f32>(0.1, 0.4, 0.3); // Verde oscuro
-            }
-            
-            let ring_alpha = (1.0 - pow(y_abs / 0.18, 1.2)) * 0.95;
-            let ring_bright = 0.9 + brightness_var * 0.2;
-            
-            planet_color = mix(planet_color, ring_color, ring_alpha);
-            planet_color *= ring_bright;
-        }
-    }
-    
-    // Sombra de anillos en el planeta
-    if (y_abs < 0.2 && ring_dist < 0.9) {
-        let shadow_bands = sin(ring_dist * 50.0) * 0.5 + 0.5;
-        let shadow = 0.6 + shadow_bands * 0.3;
-        planet_color *= shadow;
-    }
-    
-    return planet_color;
+    return mix(color1, color2, bands);
 }
 
 // SHADER 5: PLANETA VOLCÁNICO
-fn volcanic_planet_shader(pos: vec3<f32>, time: f32) -> vec3<f32> {
+// El canal alfa vale 1 en la lava y 0 en la roca: la lava es emisiva
+fn volcanic_planet_shader(pos: vec3<f32>, time: f32) -> vec4<f32> {
     let p = pos * 4.0;
     
     // Superficie con Voronoi para grietas
@@ -353,7 +323,7 @@ fn volcanic_planet_shader(pos: vec3<f32>, time: f32) -> vec3<f32> {
         }
     }
     
-    return color;
+    return vec4<f32>(color, select(0.0, 1.0, is_lava));
 }
 
 // SHADER 6: LUNA
@@ -387,40 +357,267 @@ fn moon_shader(pos: vec3<f32>) -> vec3<f32> {
     return color;
 }
 
-// Add star shader (case 7)
+// SHADER 8: ANILLOS (malla plana adjunta al planeta)
+// Perfil radial parecido al de Saturno, con t = 0 en el borde interior y 1 en
+// el exterior y `radius` en radios del planeta: anillo C tenue, anillo B denso,
+// división de Cassini y anillo A cortado por la división de Encke. rgb es el
+// color y a la opacidad. shaders.rs tiene la misma función para la CPU.
+fn ring_profile(t: f32, radius: f32) -> vec4<f32> {
+    if (t < 0.0 || t > 1.0) {
+        return vec4<f32>(0.0);
+    }
+    
+    var opacity: f32;
+    if (t < 0.22) {
+        opacity = mix(0.08, 0.3, t / 0.22); // Anillo C
+    } else if (t < 0.55) {
+        opacity = 0.9;                      // Anillo B
+    } else if (t < 0.6) {
+        opacity = 0.04;                     // División de Cassini
+    } else if (t > 0.86 && t < 0.875) {
+        opacity = 0.0;                      // División de Encke
+    } else {
+        opacity = 0.65;                     // Anillo A
+    }
+    opacity *= 1.0 - smoothstep(0.93, 1.0, t);
+    
+    // Bandas finas de color y densidad
+    let bands = sin(radius * 50.0) * 0.5 + 0.5;
+    opacity *= 0.8 + bands * 0.2;
+    let color = mix(vec3<f32>(0.7, 0.63, 0.49), vec3<f32>(0.96, 0.88, 0.75), bands);
+    return vec4<f32>(color, opacity);
+}
+
+fn ring_shader(pos: vec3<f32>) -> vec4<f32> {
+    let inner = object.params.x;
+    let outer = object.params.y;
+    let radius = length(pos.xz);
+    return ring_profile((radius - inner) / (outer - inner), radius);
+}
+
+// SHADER 7: ESTRELLA
 fn star_shader(pos: vec3<f32>, time: f32) -> vec3<f32> {
-    let dist = length(pos);
-    let glow = pow(1.0 - dist, 8.0);
+    // pos está normalizada, así que el brillo no puede depender de su longitud
     let twinkle = sin(time * 3.0 + pos.x * 10.0 + pos.y * 8.0) * 0.5 + 0.5;
-    return vec3<f32>(1.0, 1.0, 1.0) * glow * (0.7 + twinkle * 0.3);
+    return vec3<f32>(1.0, 1.0, 1.0) * (0.7 + twinkle * 0.3);
+}
+
+// Luz en un punto: dirección normalizada hacia ella e irradiancia
+struct LightSample {
+    direction: vec3<f32>,
+    irradiance: f32,
+}
+
+// El sol de la escena como luz puntual (con atenuación 1/d² opcional), o una
+// luz direccional si la escena no tiene sol, con los eclipses de los oclusores
+fn light_at(world_pos: vec3<f32>) -> LightSample {
+    if (uniforms.light.w == 0.0) {
+        let direction = uniforms.light.xyz;
+        return LightSample(direction, uniforms.light_intensity * eclipse(world_pos, direction));
+    }
+    let to_light = uniforms.light.xyz - world_pos;
+    let distance_squared = max(dot(to_light, to_light), 1e-6);
+    var irradiance = uniforms.light_intensity;
+    if (uniforms.light_inverse_square != 0u) {
+        irradiance /= distance_squared;
+    }
+    let direction = to_light * inverseSqrt(distance_squared);
+    return LightSample(direction, irradiance * eclipse(world_pos, direction));
+}
+
+// Radio angular del sol visto desde el punto; 0 para la luz direccional
+fn sun_angular_radius(world_pos: vec3<f32>) -> f32 {
+    if (uniforms.light.w == 0.0) {
+        return 0.0;
+    }
+    let distance = max(length(uniforms.light.xyz - world_pos), uniforms.light_radius);
+    if (distance <= 0.0) {
+        return 0.0;
+    }
+    return asin(uniforms.light_radius / distance);
+}
+
+// Fracción del disco del sol (radio angular `sun`) que tapa un disco de radio
+// `occluder` a `separation`: el área de la lente entre los dos círculos. La misma
+// cuenta que covered_fraction en simulation.rs.
+fn covered_fraction(sun: f32, occluder: f32, separation: f32) -> f32 {
+    if (separation >= sun + occluder) {
+        return 0.0;
+    }
+    if (separation <= abs(occluder - sun)) {
+        if (occluder >= sun) {
+            return 1.0;
+        }
+        return (occluder / sun) * (occluder / sun);
+    }
+    let r1 = sun;
+    let r2 = occluder;
+    let d = separation;
+    let a1 = r1 * r1 * acos(clamp((d * d + r1 * r1 - r2 * r2) / (2.0 * d * r1), -1.0, 1.0));
+    let a2 = r2 * r2 * acos(clamp((d * d + r2 * r2 - r1 * r1) / (2.0 * d * r2), -1.0, 1.0));
+    let a3 = 0.5 * sqrt(max((-d + r1 + r2) * (d + r1 - r2) * (d - r1 + r2) * (d + r1 + r2), 0.0));
+    return clamp((a1 + a2 - a3) / (PI * r1 * r1), 0.0, 1.0);
+}
+
+// Fracción del sol que se ve desde el punto, con la sombra de cada oclusor entre
+// los dos; el cuerpo que contiene al punto no cuenta
+fn eclipse(world_pos: vec3<f32>, direction: vec3<f32>) -> f32 {
+    var sun_distance = 1e30;
+    if (uniforms.light.w != 0.0) {
+        sun_distance = length(uniforms.light.xyz - world_pos);
+    }
+    let sun = sun_angular_radius(world_pos);
+    
+    var visible = 1.0;
+    for (var i = 0u; i < uniforms.occluder_count; i++) {
+        let occluder = uniforms.occluders[i];
+        let to_occluder = occluder.xyz - world_pos;
+        let distance = length(to_occluder);
+        let along = dot(to_occluder, direction);
+        if (distance <= occluder.w * 1.001 || along <= 0.0 || along >= sun_distance) {
+            continue;
+        }
+        let angle = asin(occluder.w / distance);
+        let separation = atan2(length(cross(to_occluder, direction)), along);
+        visible *= 1.0 - covered_fraction(sun, angle, separation);
+    }
+    return visible;
+}
+
+// Dirección de mundo llevada al espacio del objeto. El modelo es rotación por
+// escala uniforme, así que basta la traspuesta y normalizar.
+fn to_local_direction(direction: vec3<f32>) -> vec3<f32> {
+    let m = mat3x3<f32>(object.model[0].xyz, object.model[1].xyz, object.model[2].xyz);
+    return normalize(transpose(m) * direction);
+}
+
+// Luz que deja pasar el anillo hacia un punto del cuerpo, en espacio local del
+// cuerpo: el anillo está en el plano y = 0 entre `inner` y `outer`. El perfil se
+// promedia en el ancho de la penumbra, que da el radio angular `sun` del sol.
+fn ring_transmittance(local_pos: vec3<f32>, local_light: vec3<f32>, sun: f32, inner: f32, outer: f32) -> f32 {
+    if (abs(local_light.y) < 1e-4) {
+        return 1.0;
+    }
+    let distance = -local_pos.y / local_light.y;
+    if (distance <= 0.0) {
+        return 1.0;
+    }
+    let radius = length((local_pos + local_light * distance).xz);
+    let penumbra = distance * tan(sun);
+    var opacity = 0.0;
+    for (var i = 0; i < 5; i++) {
+        let r = radius + (f32(i) * 0.5 - 1.0) * penumbra;
+        opacity += ring_profile((r - inner) / (outer - inner), r).a;
+    }
+    return 1.0 - opacity / 5.0;
+}
+
+// Resultado de sombrear un cuerpo: la luz reflejada y la que emite por sí mismo.
+// La emisión también alimenta el bloom (post.wgsl).
+struct Shaded {
+    color: vec3<f32>,
+    emission: vec3<f32>,
+}
+
+// Brillo de las superficies emisivas; por encima de 1 para que el bloom las destaque
+const SUN_EMISSION: f32 = 3.0;
+const STAR_EMISSION: f32 = 2.0;
+const LAVA_EMISSION: f32 = 2.0;
+
+// Color de un cuerpo según su tipo (constantes SHADER_*), sin el tinte de la
+// escena. parity.wgsl lo llama desde un compute shader para compararlo con
+// shaders.rs, así que no puede leer `object` ni descartar.
+fn shade_body(shader_type: u32, pos: vec3<f32>, normal: vec3<f32>, light: LightSample, time: f32) -> Shaded {
+    let none = vec3<f32>(0.0);
+    
+    // El sol y las estrellas son la fuente de luz: no se iluminan a sí mismos
+    switch shader_type {
+        case SHADER_SUN: {
+            return Shaded(none, sun_shader(pos, time) * SUN_EMISSION);
+        }
+        case SHADER_ROCKY: {
+            return Shaded(lighting(rocky_planet_shader(pos, time), normal, light), none);
+        }
+        case SHADER_GAS_GIANT: {
+            return Shaded(lighting(gas_giant_shader(pos, time), normal, light), none);
+        }
+        case SHADER_RINGED: {
+            return Shaded(lighting(ringed_planet_shader(pos, time), normal, light), none);
+        }
+        case SHADER_VOLCANIC: {
+            let surface = volcanic_planet_shader(pos, time);
+            let lava = surface.a;
+            let rock = lighting(surface.rgb, normal, light) * (1.0 - lava);
+            return Shaded(rock, surface.rgb * lava * LAVA_EMISSION);
+        }
+        case SHADER_MOON: {
+            return Shaded(lighting(moon_shader(pos), normal, light), none);
+        }
+        case SHADER_STAR: {
+            return Shaded(none, star_shader(pos, time) * STAR_EMISSION);
+        }
+        default: {
+            return Shaded(vec3<f32>(1.0, 0.0, 1.0), none);
+        }
+    }
+}
+
+// Difusa con un mínimo de luz ambiente para el lado nocturno
+fn lighting(color: vec3<f32>, normal: vec3<f32>, light: LightSample) -> vec3<f32> {
+    let diffuse = max(dot(normal, light.direction) * light.irradiance, 0.15);
+    return color * mix(1.0, diffuse, 0.7);
+}
+
+// Dos destinos HDR: el color completo y, aparte, sólo la emisión para el bloom
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) emission: vec4<f32>,
 }
 
 @fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let pos = normalize(input.world_pos);
+fn fs_main(input: VertexOutput) -> FragmentOutput {
+    let pos = normalize(input.local_pos);
     let normal = normalize(input.normal);
     let time = uniforms.time;
+    var light = light_at(input.world_pos);
     
-    var color: vec3<f32>;
+    // Sombra de los anillos propios sobre el cuerpo
+    if (object.params.z > 0.0) {
+        let local_light = to_local_direction(light.direction);
+        let sun = sun_angular_radius(input.world_pos);
+        light.irradiance *= ring_transmittance(pos, local_light, sun, object.params.x, object.params.y);
+    }
+    
+    let shaded = shade_body(object.shader_type, pos, normal, light, time);
     
-    // Seleccionar shader según tipo
-    switch uniforms.shader_type {
-        case 1u: { color = sun_shader(pos, time); }
-        case 2u: { color = rocky_planet_shader(pos, time); }
-        case 3u: { color = gas_giant_shader(pos, time); }
-        case 4u: { color = ringed_planet_shader(pos, time); }
-        case 5u: { color = volcanic_planet_shader(pos, time); }
-        case 6u: { color = moon_shader(pos); }
-        case 7u: { color = star_shader(pos, time); }
-        default: { color = vec3<f32>(1.0, 0.0, 1.0); }
+    // Tinte definido por la escena
+    var output: FragmentOutput;
+    output.color = vec4<f32>((shaded.color + shaded.emission) * object.color, 1.0);
+    output.emission = vec4<f32>(shaded.emission * object.color, 1.0);
+    return output;
+}
+
+// Anillos con transparencia. Cada anillo se dibuja en dos instancias: la mitad
+// más lejana que el centro del planeta antes de su atmósfera y la cercana
+// después, para que la mezcla quede en orden de atrás hacia adelante.
+@fragment
+fn fs_ring(input: VertexOutput) -> FragmentOutput {
+    let far = input.view_depth >= input.center_depth;
+    if (far != (input.ring_half == 0u)) {
+        discard;
     }
     
-    // Iluminación básica
-    let light_dir = normalize(vec3<f32>(1.0, 0.5, 0.8));
-    let diffuse = max(dot(normal, light_dir), 0.15);
+    let surface = ring_shader(input.local_pos);
+    if (surface.a <= 0.0) {
+        discard;
+    }
     
-    // Auto-emisión para sol y lava
-    let emission = select(1.0, diffuse, uniforms.shader_type != 1u && uniforms.shader_type != 5u);
+    // El planeta y sus lunas les hacen sombra desde light_at, como oclusores
+    let light = light_at(input.world_pos);
+    let color = lighting(surface.rgb, normalize(input.normal), light);
     
-    return vec4<f32>(color * mix(1.0, diffuse, 0.7), 1.0);
+    var output: FragmentOutput;
+    output.color = vec4<f32>(color * object.color, surface.a);
+    output.emission = vec4<f32>(0.0);
+    return output;
 }
@@ -0,0 +1,123 @@
+// shader_kind.rs - Tipos de shader de los cuerpos, comunes a CPU y GPU
+//
+// `ShaderKind` es la única definición de la numeración: `shader.wgsl` no usa
+// números sino las constantes `SHADER_*` que genera `wgsl_constants` y que
+// `renderer::wgsl_with_prelude` agrega al final del código del shader, y
+// `shaders.rs` recibe el enum directamente. Los archivos de escena lo nombran
+// con `FromStr`.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderKind {
+    Sun = 1,
+    Rocky = 2,
+    GasGiant = 3,
+    Ringed = 4,
+    Volcanic = 5,
+    Moon = 6,
+    Star = 7,
+    // Malla plana de los anillos; no se puede pedir desde una escena, la agrega el renderer
+    Ring = 8,
+}
+
+impl ShaderKind {
+    pub const ALL: [ShaderKind; 8] = [
+        ShaderKind::Sun,
+        ShaderKind::Rocky,
+        ShaderKind::GasGiant,
+        ShaderKind::Ringed,
+        ShaderKind::Volcanic,
+        ShaderKind::Moon,
+        ShaderKind::Star,
+        ShaderKind::Ring,
+    ];
+
+    // Nombre en los archivos de escena
+    pub fn name(self) -> &'static str {
+        match self {
+            ShaderKind::Sun => "sun",
+            ShaderKind::Rocky => "rocky",
+            ShaderKind::GasGiant => "gas_giant",
+            ShaderKind::Ringed => "ringed",
+            ShaderKind::Volcanic => "volcanic",
+            ShaderKind::Moon => "moon",
+            ShaderKind::Star => "star",
+            ShaderKind::Ring => "ring",
+        }
+    }
+
+    // Valor de `Object.shader_type` en shader.wgsl
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    // Tipos que un cuerpo puede declarar en la escena
+    pub fn for_bodies() -> impl Iterator<Item = ShaderKind> {
+        Self::ALL.into_iter().filter(|&kind| kind != ShaderKind::Ring)
+    }
+
+    // `const SHADER_SUN: u32 = 1u;` etc., para agregar a los .wgsl
+    pub fn wgsl_constants() -> String {
+        let mut block = String::from("// Generado por ShaderKind::wgsl_constants (shader_kind.rs)\n");
+        for kind in Self::ALL {
+            block.push_str(&format!(
+                "const SHADER_{}: u32 = {}u;\n",
+                kind.name().to_uppercase(),
+                kind.id()
+            ));
+        }
+        block
+    }
+}
+
+impl fmt::Display for ShaderKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ShaderKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::for_bodies().find(|kind| kind.name() == name).ok_or_else(|| {
+            let expected: Vec<_> = Self::for_bodies().map(ShaderKind::name).collect();
+            format!(
+                "shader desconocido '{}' (esperado: {})",
+                name,
+                expected.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_names_round_trip() {
+        for kind in ShaderKind::for_bodies() {
+            assert_eq!(kind.name().parse::<ShaderKind>(), Ok(kind));
+        }
+    }
+
+    #[test]
+    fn ring_is_not_a_scene_shader() {
+        assert!("ring".parse::<ShaderKind>().is_err());
+        assert!("plasma".parse::<ShaderKind>().unwrap_err().contains("gas_giant"));
+    }
+
+    #[test]
+    fn ids_are_unique_and_match_constants() {
+        let constants = ShaderKind::wgsl_constants();
+        for (i, kind) in ShaderKind::ALL.iter().enumerate() {
+            assert!(ShaderKind::ALL[..i].iter().all(|other| other.id() != kind.id()));
+            let line = format!("const SHADER_{}: u32 = {}u;", kind.name().to_uppercase(), kind.id());
+            assert!(constants.contains(&line), "{}", line);
+        }
+    }
+}
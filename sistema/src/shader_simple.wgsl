@@ -1,83 +1,110 @@
 // shader_simple.wgsl - Shader simplificado para debug
+//
+// Vistas de diagnóstico que se alternan en tiempo de ejecución: color plano por
+// tipo con normales de cara, normales como color, cuadrícula UV y alambre (el
+// alambre usa este mismo fs_main con una malla de líneas). Usa los mismos grupos
+// que shader.wgsl, así que comparte buffers y layout con el pipeline normal, y
+// las constantes SHADER_* de ShaderKind::wgsl_constants (shader_kind.rs).
 
-struct Uniforms {
-    time: f32,
-    shader_type: u32,
-    resolution: vec2<f32>,
-}
+// Valores de DebugView en renderer.rs
+const DEBUG_FLAT: u32 = 1u;
+const DEBUG_NORMALS: u32 = 2u;
+const DEBUG_UV_CHECKER: u32 = 3u;
+const DEBUG_WIREFRAME: u32 = 4u;
 
-struct ModelTransform {
-    model_matrix: mat4x4<f32>,
-    view_proj_matrix: mat4x4<f32>,
+// `struct Uniforms` la genera renderer::uniforms_wgsl y se agrega al final del código
+
+struct Object {
+    model: mat4x4<f32>,
+    color: vec3<f32>,
+    shader_type: u32,
+    params: vec4<f32>,
 }
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
 @group(1) @binding(0)
-var<uniform> transform: ModelTransform;
+var<uniform> object: Object;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) world_pos: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
 }
 
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
-    
-    // Transformar posición
-    let world_pos = transform.model_matrix * vec4<f32>(input.position, 1.0);
+
+    let world_pos = object.model * vec4<f32>(input.position, 1.0);
+    output.clip_position = uniforms.view_proj * world_pos;
     output.world_pos = world_pos.xyz;
-    
-    // Transformar normal
-    let normal_matrix = mat3x3<f32>(
-        transform.model_matrix[0].xyz,
-        transform.model_matrix[1].xyz,
-        transform.model_matrix[2].xyz
-    );
-    output.normal = normalize(normal_matrix * input.normal);
-    
-    // Aplicar proyección
-    output.clip_position = transform.view_proj_matrix * world_pos;
-    
+    output.normal = normalize((object.model * vec4<f32>(input.normal, 0.0)).xyz);
+    output.uv = input.uv;
+
     return output;
 }
 
+// Colores simples según shader_type
+fn flat_color(shader_type: u32) -> vec3<f32> {
+    switch shader_type {
+        case SHADER_SUN: { return vec3<f32>(1.0, 0.9, 0.2); }       // Sol - amarillo
+        case SHADER_ROCKY: { return vec3<f32>(0.8, 0.4, 0.2); }     // Rocoso - naranja
+        case SHADER_GAS_GIANT: { return vec3<f32>(0.3, 0.6, 0.9); } // Gaseoso - azul claro
+        case SHADER_RINGED: { return vec3<f32>(0.9, 0.7, 0.3); }    // Con anillos - amarillo/marrón
+        case SHADER_VOLCANIC: { return vec3<f32>(0.9, 0.2, 0.1); }  // Volcánico - rojo
+        case SHADER_STAR: { return vec3<f32>(1.0, 1.0, 1.0); }      // Estrella - blanco
+        case SHADER_RING: { return vec3<f32>(0.6, 0.5, 0.8); }      // Anillos - lila
+        default: { return vec3<f32>(0.7, 0.7, 0.7); }               // Luna - gris
+    }
+}
+
+// Mismos destinos que shader.wgsl; las vistas de debug no emiten luz
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) emission: vec4<f32>,
+}
+
 @fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    // Colores simples según shader_type
+fn fs_main(input: VertexOutput) -> FragmentOutput {
     var color: vec3<f32>;
-    
-    if (uniforms.shader_type == 1u) {
-        // Sol - amarillo
-        color = vec3<f32>(1.0, 0.9, 0.2);
-    } else if (uniforms.shader_type == 2u) {
-        // Planeta rocoso - naranja
-        color = vec3<f32>(0.8, 0.4, 0.2);
-    } else if (uniforms.shader_type == 3u) {
-        // Gigante gaseoso - azul claro
-        color = vec3<f32>(0.3, 0.6, 0.9);
-    } else if (uniforms.shader_type == 4u) {
-        // Planeta con anillos - amarillo/marrón
-        color = vec3<f32>(0.9, 0.7, 0.3);
-    } else if (uniforms.shader_type == 5u) {
-        // Volcánico - rojo
-        color = vec3<f32>(0.9, 0.2, 0.1);
-    } else {
-        // Luna - gris
-        color = vec3<f32>(0.7, 0.7, 0.7);
+
+    switch uniforms.debug_view {
+        case DEBUG_NORMALS: {
+            // Normal interpolada en espacio de mundo, de [-1, 1] a [0, 1]
+            color = normalize(input.normal) * 0.5 + 0.5;
+        }
+        case DEBUG_UV_CHECKER: {
+            // UV de la malla en 16 x 8 casillas; u en rojo y v en verde para ver
+            // la orientación. Los anillos van de 0 a 1 del borde interior al exterior.
+            let uv = input.uv;
+            let cell = vec2<i32>(floor(uv * vec2<f32>(16.0, 8.0)));
+            let checker = select(0.35, 1.0, (cell.x + cell.y) % 2 == 0);
+            color = vec3<f32>(uv.x, uv.y, 1.0) * checker;
+        }
+        case DEBUG_WIREFRAME: {
+            color = flat_color(object.shader_type);
+        }
+        default: {
+            // Normal de la cara a partir de las derivadas de la posición: deja
+            // ver cada triángulo y si su orientación es la esperada
+            let face_normal = normalize(cross(dpdy(input.world_pos), dpdx(input.world_pos)));
+            let light_dir = normalize(vec3<f32>(1.0, 1.0, 1.0));
+            let diffuse = max(dot(face_normal, light_dir), 0.2);
+            color = flat_color(object.shader_type) * diffuse;
+        }
     }
-    
-    // Iluminación simple
-    let light_dir = normalize(vec3<f32>(1.0, 1.0, 1.0));
-    let diffuse = max(dot(normalize(input.normal), light_dir), 0.2);
-    
-    return vec4<f32>(color * diffuse, 1.0);
+
+    var output: FragmentOutput;
+    output.color = vec4<f32>(color, 1.0);
+    output.emission = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    return output;
 }
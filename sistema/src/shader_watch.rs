@@ -0,0 +1,118 @@
+// shader_watch.rs - Recarga de shader.wgsl en caliente (modo --watch)
+//
+// En lugar del shader incluido al compilar, se lee el archivo del árbol de
+// fuentes y se vuelve a leer cada vez que cambia su fecha de modificación. La
+// consulta es un `stat` a lo sumo cuatro veces por segundo, sin hilos ni
+// dependencias extra. Quien llama decide qué hacer con el código nuevo; si no
+// compila, el renderer mantiene el último pipeline válido.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// El shader de los cuerpos dentro del árbol de fuentes
+pub const SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader.wgsl");
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct ShaderWatcher {
+    path: PathBuf,
+    // None hasta la primera lectura, para que `poll` cargue el archivo al empezar
+    modified: Option<SystemTime>,
+    last_poll: Option<Instant>,
+    // Ya se avisó que el archivo no se puede leer; se vuelve a avisar tras leerlo bien
+    failed: bool,
+}
+
+impl ShaderWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ShaderWatcher {
+            path: path.into(),
+            modified: None,
+            last_poll: None,
+            failed: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Devuelve el contenido del archivo si cambió desde la última lectura. Mientras
+    // un editor lo reescribe puede no existir por un momento: se reintenta después,
+    // y si sigue faltando (p. ej. el binario corre lejos del árbol de fuentes) se
+    // avisa en la consola.
+    pub fn poll(&mut self) -> Option<String> {
+        if self.last_poll.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+            return None;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => return self.report(e),
+        };
+        if self.modified == Some(modified) {
+            self.failed = false;
+            return None;
+        }
+        let source = match std::fs::read_to_string(&self.path) {
+            Ok(source) => source,
+            Err(e) => return self.report(e),
+        };
+        self.modified = Some(modified);
+        self.failed = false;
+        Some(source)
+    }
+
+    // Un solo aviso por racha de errores, no uno por consulta
+    fn report(&mut self, error: std::io::Error) -> Option<String> {
+        if !self.failed {
+            eprintln!("--watch: no se pudo leer {}: {}", self.path.display(), error);
+            self.failed = true;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn poll_returns_the_source_only_when_the_mtime_changes() {
+        let path = std::env::temp_dir().join(format!("shader_watch_{}.wgsl", std::process::id()));
+        let write = |source: &str, modified: SystemTime| {
+            let mut file = File::create(&path).unwrap();
+            file.write_all(source.as_bytes()).unwrap();
+            file.set_modified(modified).unwrap();
+        };
+        let mut watcher = ShaderWatcher::new(&path);
+        // Sin esperar POLL_INTERVAL entre consultas
+        let mut poll = || {
+            watcher.last_poll = None;
+            watcher.poll()
+        };
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        write("a", start);
+        assert_eq!(poll().as_deref(), Some("a"));
+        assert_eq!(poll(), None);
+
+        // Contenido nuevo con la misma fecha: no se nota hasta que cambia la fecha
+        write("b", start);
+        assert_eq!(poll(), None);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(poll().as_deref(), Some("b"));
+        assert_eq!(poll(), None);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(poll(), None);
+    }
+}
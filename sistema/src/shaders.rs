@@ -1,8 +1,11 @@
 use nalgebra_glm::{Vec3, Vec4, Mat4};
 use crate::vertex::Vertex;
-use crate::Uniforms;
+use crate::software::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
+use crate::scene::Rings;
+use crate::shader_kind::ShaderKind;
+use sistema::noise::{SEA_LEVEL, terrain_height, voronoi_simple};
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let position = Vec4::new(
@@ -24,8 +27,8 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
 
     let screen_position = uniforms.viewport_matrix * ndc_position;
 
-    let model_mat3 = Mat4::identity();
-    let normal_matrix = model_mat3;
+    // Inversa transpuesta de la matriz de modelo: normales en espacio de mundo
+    let normal_matrix = uniforms.model_matrix.try_inverse().unwrap_or_else(Mat4::identity).transpose();
 
     let normal_vector = Vec4::new(
         vertex.normal.x,
@@ -45,58 +48,114 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: u8) -> Color {
-    match shader_type {
-        1 => sun_shader(fragment, uniforms),
-        2 => rocky_planet_shader(fragment, uniforms),
-        3 => gas_giant_shader(fragment, uniforms),
-        4 => ringed_planet_shader(fragment, uniforms),
-        5 => planet_with_moon_shader(fragment, uniforms),
-        6 => moon_shader(fragment, uniforms),
-        _ => Color::new(255, 255, 255),
-    }
+// None para los tipos que sólo existen en shader.wgsl (estrella y anillos)
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, kind: ShaderKind) -> Option<Color> {
+    let color = match kind {
+        ShaderKind::Sun => sun_shader(fragment, uniforms),
+        ShaderKind::Rocky => rocky_planet_shader(fragment, uniforms),
+        ShaderKind::GasGiant => gas_giant_shader(fragment, uniforms),
+        ShaderKind::Ringed => ringed_planet_shader(fragment, uniforms),
+        ShaderKind::Volcanic => volcanic_planet_shader(fragment, uniforms),
+        ShaderKind::Moon => moon_shader(fragment, uniforms),
+        ShaderKind::Star | ShaderKind::Ring => return None,
+    };
+    Some(color)
 }
 
-// Funciones matemáticas rápidas para patrones procedurales
-#[inline(always)]
-fn fast_noise(p: Vec3) -> f32 {
-    ((p.x * 12.9898 + p.y * 78.233 + p.z * 37.719).sin() * 43758.5453).fract()
+// Dirección hacia la luz e irradiancia en el punto del fragmento, en espacio de
+// mundo, con los eclipses de otros cuerpos y la sombra de los anillos propios
+fn light_at(fragment: &Fragment, uniforms: &Uniforms) -> (Vec3, f32) {
+    let p = fragment.vertex_position;
+    let world = (uniforms.model_matrix * Vec4::new(p.x, p.y, p.z, 1.0)).xyz();
+    let (direction, irradiance) = uniforms.light.at(&world);
+    let irradiance = irradiance * uniforms.light.visibility(&world, uniforms.occluders);
+    match &uniforms.rings {
+        Some(rings) => {
+            let local_light = to_local_direction(&direction, uniforms);
+            let sun = uniforms.light.angular_radius(&world);
+            (direction, irradiance * ring_transmittance(&p, &local_light, sun, rings))
+        }
+        None => (direction, irradiance),
+    }
 }
 
-#[inline(always)]
-fn pattern1(p: Vec3, time: f32) -> f32 {
-    let a = (p.x * 3.0 + time).sin();
-    let b = (p.y * 3.0 - time * 0.5).cos();
-    let c = (p.z * 3.0 + time * 0.3).sin();
-    (a + b + c) * 0.333
+// Dirección de mundo llevada al espacio del objeto (rotación por escala uniforme)
+fn to_local_direction(direction: &Vec3, uniforms: &Uniforms) -> Vec3 {
+    let m = uniforms.model_matrix.fixed_view::<3, 3>(0, 0);
+    (m.transpose() * direction).normalize()
 }
 
-#[inline(always)]
-fn pattern2(p: Vec3, time: f32) -> f32 {
-    let freq = 8.0;
-    ((p.x * freq).sin() * (p.y * freq).cos() + (p.z * freq + time).sin()) * 0.5
+// Perfil radial de los anillos: el mismo que ring_profile en shader.wgsl. `t` es 0
+// en el borde interior y 1 en el exterior, `radius` está en radios del planeta.
+// Devuelve el color y la opacidad.
+pub fn ring_profile(t: f32, radius: f32) -> (Color, f32) {
+    if !(0.0..=1.0).contains(&t) {
+        return (Color::new(0, 0, 0), 0.0);
+    }
+
+    let mut opacity = if t < 0.22 {
+        0.08 + (0.3 - 0.08) * t / 0.22 // Anillo C
+    } else if t < 0.55 {
+        0.9 // Anillo B
+    } else if t < 0.6 {
+        0.04 // División de Cassini
+    } else if t > 0.86 && t < 0.875 {
+        0.0 // División de Encke
+    } else {
+        0.65 // Anillo A
+    };
+    let edge = ((t - 0.93) / 0.07).clamp(0.0, 1.0);
+    opacity *= 1.0 - edge * edge * (3.0 - 2.0 * edge);
+
+    // Bandas finas de color y densidad
+    let bands = (radius * 50.0).sin() * 0.5 + 0.5;
+    opacity *= 0.8 + bands * 0.2;
+    let color = Color::from_float(0.7, 0.63, 0.49).lerp(&Color::from_float(0.96, 0.88, 0.75), bands);
+    (color, opacity)
 }
 
-#[inline(always)]
-fn voronoi_simple(p: Vec3) -> f32 {
-    let pi = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
-    let pf = Vec3::new(p.x.fract(), p.y.fract(), p.z.fract());
-    
-    let mut min_dist: f32 = 2.0;
-    for i in -1..=1 {
-        for j in -1..=1 {
-            let neighbor = Vec3::new(i as f32, j as f32, 0.0);
-            let point = neighbor + Vec3::new(
-                fast_noise(pi + neighbor),
-                fast_noise(pi + neighbor + Vec3::new(0.1, 0.1, 0.1)),
-                0.0
-            );
-            let diff = point - pf;
-            let dist = diff.x * diff.x + diff.y * diff.y;
-            min_dist = min_dist.min(dist);
-        }
+// Luz que dejan pasar los anillos (plano y = 0 local) hacia un punto del cuerpo.
+// El sol no es un punto: se promedia el perfil en el ancho de la penumbra, que
+// crece con la distancia al anillo y el radio angular `sun`.
+fn ring_transmittance(local_pos: &Vec3, local_light: &Vec3, sun: f32, rings: &Rings) -> f32 {
+    if local_light.y.abs() < 1e-4 {
+        return 1.0;
     }
-    min_dist.sqrt()
+    let distance = -local_pos.y / local_light.y;
+    if distance <= 0.0 {
+        return 1.0;
+    }
+    let hit = local_pos + local_light * distance;
+    let radius = (hit.x * hit.x + hit.z * hit.z).sqrt();
+    let penumbra = distance * sun.tan();
+    let opacity: f32 = RING_SHADOW_SAMPLES
+        .iter()
+        .map(|offset| {
+            let r = radius + offset * penumbra;
+            ring_profile((r - rings.inner) / (rings.outer - rings.inner), r).1
+        })
+        .sum();
+    1.0 - opacity / RING_SHADOW_SAMPLES.len() as f32
+}
+
+// Posiciones de las muestras a lo ancho de la penumbra de los anillos
+const RING_SHADOW_SAMPLES: [f32; 5] = [-1.0, -0.5, 0.0, 0.5, 1.0];
+
+// Anillos en la malla plana del nodo de anillos: color ya iluminado y opacidad.
+// El planeta y sus lunas les hacen sombra como oclusores.
+pub fn ring_shader(fragment: &Fragment, uniforms: &Uniforms, rings: &Rings) -> (Color, f32) {
+    let p = fragment.vertex_position;
+    let radius = (p.x * p.x + p.z * p.z).sqrt();
+    let (color, opacity) = ring_profile((radius - rings.inner) / (rings.outer - rings.inner), radius);
+
+    let world = (uniforms.model_matrix * Vec4::new(p.x, p.y, p.z, 1.0)).xyz();
+    let (light_dir, irradiance) = uniforms.light.at(&world);
+    let shadow = uniforms.light.visibility(&world, uniforms.occluders);
+    let normal = fragment.normal.normalize();
+    // Como `lighting` en shader.wgsl: la cara a la sombra conserva parte del color
+    let diffuse = (normal.dot(&light_dir) * irradiance * shadow).max(0.15);
+
+    (color.mul(1.0 + (diffuse - 1.0) * 0.7), opacity)
 }
 
 // ===== SHADER 1: SOL CON PLASMA ANIMADO =====
@@ -117,7 +176,7 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let spiral = ((angle * 8.0 + radius * 6.0 - time * 3.0).sin() + 1.0) * 0.5;
     
     // Manchas solares (zonas oscuras)
-    let spot_pattern = ((pos.x * 8.0).sin() * (pos.y * 8.0).cos() + (pos.z * 8.0 + time * 0.1).sin());
+    let spot_pattern = (pos.x * 8.0).sin() * (pos.y * 8.0).cos() + (pos.z * 8.0 + time * 0.1).sin();
     let spots = if spot_pattern > 0.8 { 0.5 } else { 1.0 };
     
     // Pulsación de corona
@@ -146,20 +205,16 @@ fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let pos = fragment.vertex_position * 5.0;
     let time = uniforms.time as f32 * 0.005;
     
-    // Generar continentes con patrón Voronoi
-    let continents = voronoi_simple(pos * 0.8);
-    let mountains = ((pos.x * 10.0).sin() * (pos.y * 10.0).cos() + (pos.z * 10.0).sin() + 1.0) * 0.5;
-    
-    let terrain_height = continents * 0.7 + mountains * 0.3;
+    // Continentes con patrón Voronoi y cordilleras (noise.rs, también lo usa bin/meshgen.rs)
+    let height = terrain_height(pos);
     
-    let is_ocean = terrain_height < 0.35;
-    let is_land = terrain_height >= 0.35 && terrain_height < 0.55;
-    let is_mountain = terrain_height >= 0.55 && terrain_height < 0.65;
-    let is_snow = terrain_height >= 0.65;
+    let is_ocean = height < SEA_LEVEL;
+    let is_mountain = (0.55..0.65).contains(&height);
+    let is_snow = height >= 0.65;
     
     // Colores base del terreno
     let mut color = if is_ocean {
-        let depth = (0.35 - terrain_height) * 5.0;
+        let depth = (SEA_LEVEL - height) * 5.0;
         if depth > 0.6 {
             Color::new(10, 40, 100) // Océano profundo
         } else {
@@ -192,18 +247,10 @@ fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         color = color.lerp(&cloud_color, density * 0.85);
     }
     
-    // Atmósfera azul
-    let dist = (pos.x * pos.x + pos.y * pos.y + pos.z * pos.z).sqrt();
-    let atmosphere = ((1.0 - dist * 0.2).max(0.0)).powf(5.0);
-    if atmosphere > 0.0 {
-        let atmo_color = Color::new(100, 150, 255);
-        color = color.lerp(&atmo_color, atmosphere * 0.4);
-    }
-    
     // Iluminación
-    let light_dir = Vec3::new(0.8, 0.5, 1.0).normalize();
+    let (light_dir, irradiance) = light_at(fragment, uniforms);
     let normal = fragment.normal.normalize();
-    let diffuse = normal.dot(&light_dir).max(0.15);
+    let diffuse = (normal.dot(&light_dir) * irradiance).max(0.15);
     
     color.mul(diffuse)
 }
@@ -266,81 +313,33 @@ fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     }
     
     // Iluminación
-    let light_dir = Vec3::new(1.0, 0.3, 0.8).normalize();
+    let (light_dir, irradiance) = light_at(fragment, uniforms);
     let normal = fragment.normal.normalize();
-    let diffuse = normal.dot(&light_dir).max(0.2);
+    let diffuse = (normal.dot(&light_dir) * irradiance).max(0.2);
     
     final_color.mul(diffuse)
 }
 
-// ===== SHADER 4: SATURNO CON ANILLOS ESPECTACULARES Y VISIBLES =====
+// ===== SHADER 4: SATURNO (los anillos son una malla aparte, ver ring_shader) =====
 fn ringed_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let pos = fragment.vertex_position * 3.0;
-    let time = uniforms.time as f32 * 0.006;
     
     // Planeta con bandas suaves
     let bands = ((pos.y * 20.0 + ((pos.x * 3.0).sin() + (pos.z * 3.0).cos()) * 0.5).sin() + 1.0) * 0.5;
     let color1 = Color::new(255, 240, 210);
     let color2 = Color::new(240, 220, 180);
-    let mut planet_color = color1.lerp(&color2, bands);
-    
-    // ANILLOS ULTRA VISIBLES
-    let ring_dist = (pos.x * pos.x + pos.z * pos.z).sqrt();
-    let y_abs = pos.y.abs();
-    
-    // Zona de anillos expandida
-    if y_abs < 0.18 && ring_dist > 0.75 && ring_dist < 2.0 {
-        // Patrones de anillos concéntricos
-        let ring_freq = ring_dist * 50.0;
-        let ring_bands = (ring_freq.sin() + 1.0) * 0.5;
-        
-        // Variación de brillo por anillo
-        let brightness_var = ((ring_dist * 30.0 + time * 3.0).sin() + 1.0) * 0.5;
-        
-        // Divisiones de Cassini (gaps oscuros)
-        let is_gap = (ring_dist > 1.0 && ring_dist < 1.15) ||
-                     (ring_dist > 1.5 && ring_dist < 1.55) ||
-                     (ring_dist > 1.75 && ring_dist < 1.78);
-        
-        if is_gap {
-            // Gaps con transparencia (mostrar planeta oscurecido)
-            planet_color = planet_color.mul(0.4);
-        } else {
-            // Anillos visibles con colores variados
-            let ring_color = if ring_bands > 0.7 {
-                Color::new(245, 225, 190) // Anillos claros
-            } else if ring_bands > 0.4 {
-                Color::new(210, 185, 145) // Anillos medios
-            } else {
-                Color::new(180, 160, 125) // Anillos oscuros
-            };
-            
-            // Transparencia basada en distancia al plano
-            let ring_alpha = (1.0 - (y_abs / 0.18).powf(1.2)) * 0.95;
-            let ring_bright = 0.9 + brightness_var * 0.2;
-            
-            planet_color = planet_color.lerp(&ring_color, ring_alpha);
-            planet_color = planet_color.mul(ring_bright);
-        }
-    }
-    
-    // Sombra de anillos sobre el planeta
-    if y_abs < 0.2 && ring_dist < 0.9 {
-        let shadow_bands = (ring_dist * 50.0).sin() * 0.5 + 0.5;
-        let shadow = 0.6 + shadow_bands * 0.3;
-        planet_color = planet_color.mul(shadow);
-    }
+    let planet_color = color1.lerp(&color2, bands);
     
     // Iluminación
-    let light_dir = Vec3::new(1.0, 0.6, 0.8).normalize();
+    let (light_dir, irradiance) = light_at(fragment, uniforms);
     let normal = fragment.normal.normalize();
-    let diffuse = normal.dot(&light_dir).max(0.25);
+    let diffuse = (normal.dot(&light_dir) * irradiance).max(0.25);
     
     planet_color.mul(diffuse)
 }
 
 // ===== SHADER 5: PLANETA VOLCÁNICO CON LAVA BRILLANTE =====
-fn planet_with_moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+fn volcanic_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let pos = fragment.vertex_position * 4.0;
     let time = uniforms.time as f32 * 0.015;
     
@@ -387,15 +386,15 @@ fn planet_with_moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     color = color.add(&glow_color);
     
     // Iluminación con auto-emisión
-    let light_dir = Vec3::new(0.8, 0.8, 1.0).normalize();
+    let (light_dir, irradiance) = light_at(fragment, uniforms);
     let normal = fragment.normal.normalize();
-    let diffuse = normal.dot(&light_dir).max(0.35);
+    let diffuse = (normal.dot(&light_dir) * irradiance).max(0.35);
     
     color.mul(diffuse * 0.5 + 0.5)
 }
 
 // ===== SHADER 6: LUNA CON CRÁTERES =====
-fn moon_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
+fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let pos = fragment.vertex_position * 5.0;
     
     // Cráteres con Voronoi
@@ -425,9 +424,9 @@ fn moon_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
     let final_color = base_color.mul(0.90 + fine_detail * 0.20);
     
     // Iluminación lunar con sombras duras
-    let light_dir = Vec3::new(1.0, 0.3, 0.8).normalize();
+    let (light_dir, irradiance) = light_at(fragment, uniforms);
     let normal = fragment.normal.normalize();
-    let diffuse = normal.dot(&light_dir).max(0.10);
+    let diffuse = (normal.dot(&light_dir) * irradiance).max(0.10);
     
     final_color.mul(diffuse)
 }
@@ -0,0 +1,304 @@
+// simulation.rs - Estado de la escena compartido por los backends de GPU y CPU
+//
+// Avanza el grafo de escena y la cámara; los renderers sólo leen las matrices de
+// mundo, la vista y la proyección que quedan aquí después de `update`.
+
+use crate::camera::{Camera, Projection};
+use crate::camera_controller::CameraController;
+use crate::scene::{BodyNodes, Scene};
+use crate::scene_graph::SceneGraph;
+use crate::shader_kind::ShaderKind;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+#[derive(Debug, Clone, Copy)]
+pub enum LightSource {
+    // Posición de mundo del sol
+    Point(Vec3),
+    // Dirección hacia la luz, cuando la escena no tiene sol
+    Directional(Vec3),
+}
+
+// Luz que ilumina los cuerpos, la misma para los shaders de CPU y de GPU
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub source: LightSource,
+    pub intensity: f32,
+    pub inverse_square: bool,
+    // Radio del sol; da el tamaño angular y con él el ancho de la penumbra
+    pub radius: f32,
+}
+
+// Cuerpo que puede tapar el sol, en espacio de mundo
+#[derive(Debug, Clone, Copy)]
+pub struct Occluder {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+// Cuerpos que proyectan sombra como máximo; el mismo límite que MAX_OCCLUDERS en shader.wgsl
+pub const MAX_OCCLUDERS: usize = 16;
+
+// Fracción del disco del sol (radio angular `sun`) que tapa un disco de radio
+// angular `occluder` cuyo centro está a `separation`: el área de la lente entre
+// los dos círculos, tratándolos como planos porque los ángulos son chicos
+fn covered_fraction(sun: f32, occluder: f32, separation: f32) -> f32 {
+    if separation >= sun + occluder {
+        return 0.0;
+    }
+    if separation <= (occluder - sun).abs() {
+        // Uno dentro del otro: eclipse total o anular
+        return if occluder >= sun { 1.0 } else { (occluder / sun).powi(2) };
+    }
+    let (r1, r2, d) = (sun, occluder, separation);
+    let a1 = r1 * r1 * ((d * d + r1 * r1 - r2 * r2) / (2.0 * d * r1)).clamp(-1.0, 1.0).acos();
+    let a2 = r2 * r2 * ((d * d + r2 * r2 - r1 * r1) / (2.0 * d * r2)).clamp(-1.0, 1.0).acos();
+    let a3 = 0.5 * ((-d + r1 + r2) * (d + r1 - r2) * (d - r1 + r2) * (d + r1 + r2)).max(0.0).sqrt();
+    ((a1 + a2 - a3) / (std::f32::consts::PI * r1 * r1)).clamp(0.0, 1.0)
+}
+
+impl Light {
+    // La dirección fija que usaban todos los shaders antes de tener un sol como luz
+    pub fn fallback() -> Light {
+        Light {
+            source: LightSource::Directional(Vec3::new(1.0, 0.5, 0.8).normalize()),
+            intensity: 1.0,
+            inverse_square: false,
+            radius: 0.0,
+        }
+    }
+
+    // Radio angular del sol visto desde `point`; 0 para la luz direccional
+    pub fn angular_radius(&self, point: &Vec3) -> f32 {
+        match self.source {
+            LightSource::Directional(_) => 0.0,
+            LightSource::Point(position) => {
+                let distance = (position - point).norm().max(self.radius);
+                if distance <= 0.0 { 0.0 } else { (self.radius / distance).asin() }
+            }
+        }
+    }
+
+    // Fracción del sol que se ve desde `point`, con la sombra de cada oclusor entre
+    // los dos. Los cuerpos que contienen al punto (el propio cuerpo) no cuentan.
+    pub fn visibility(&self, point: &Vec3, occluders: &[Occluder]) -> f32 {
+        let (direction, _) = self.at(point);
+        let sun_distance = match self.source {
+            LightSource::Point(position) => (position - point).norm(),
+            LightSource::Directional(_) => f32::INFINITY,
+        };
+        let sun = self.angular_radius(point);
+
+        let mut visible = 1.0;
+        for occluder in occluders {
+            let to_occluder = occluder.center - point;
+            let distance = to_occluder.norm();
+            let along = to_occluder.dot(&direction);
+            if distance <= occluder.radius * 1.001 || along <= 0.0 || along >= sun_distance {
+                continue;
+            }
+            let angle = (occluder.radius / distance).asin();
+            let separation = to_occluder.cross(&direction).norm().atan2(along);
+            visible *= 1.0 - covered_fraction(sun, angle, separation);
+        }
+        visible
+    }
+
+    // Dirección normalizada hacia la luz e irradiancia en `point` (espacio de mundo)
+    pub fn at(&self, point: &Vec3) -> (Vec3, f32) {
+        match self.source {
+            LightSource::Directional(direction) => (direction, self.intensity),
+            LightSource::Point(position) => {
+                let to_light = position - point;
+                let distance_squared = to_light.norm_squared().max(1e-6);
+                let irradiance = if self.inverse_square {
+                    self.intensity / distance_squared
+                } else {
+                    self.intensity
+                };
+                (to_light / distance_squared.sqrt(), irradiance)
+            }
+        }
+    }
+}
+
+pub struct Simulation {
+    pub scene: Scene,
+    scene_graph: SceneGraph,
+    pub body_nodes: Vec<BodyNodes>,
+    // Matrices de mundo de todos los nodos del grafo en el último `update`
+    pub world: Vec<Mat4>,
+    pub camera: Camera,
+    pub projection: Projection,
+    pub controller: CameraController,
+    selected: Option<usize>, // Cuerpo seguido por la cámara (None = centro del sistema)
+    pub time: f32,
+}
+
+impl Simulation {
+    pub fn new(scene: Scene, width: u32, height: u32) -> Self {
+        let (scene_graph, body_nodes) = scene.build_graph();
+        Simulation {
+            camera: Camera::new(
+                Vec3::new(0.0, 0.0, scene.camera.distance),
+                Vec3::zeros(),
+                Vec3::y(),
+            ),
+            projection: Projection::new(width, height, scene.camera.fov_y),
+            controller: CameraController::new(&scene.camera),
+            selected: None,
+            world: Vec::new(),
+            scene,
+            scene_graph,
+            body_nodes,
+            time: 0.0,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.projection.resize(width, height);
+    }
+
+    // Pasa al siguiente cuerpo a seguir y devuelve su nombre; None es el centro del sistema
+    pub fn select_next_body(&mut self) -> Option<&str> {
+        self.selected = match self.selected {
+            None if !self.scene.bodies.is_empty() => Some(0),
+            Some(i) if i + 1 < self.scene.bodies.len() => Some(i + 1),
+            _ => None,
+        };
+        self.selected.map(|i| self.scene.bodies[i].name.as_str())
+    }
+
+    // Avanza la escena a `time` segundos; `dt` sólo se usa para suavizar la cámara
+    pub fn update(&mut self, time: f32, dt: f32) {
+        self.time = time;
+
+        self.scene
+            .update_graph(&mut self.scene_graph, &self.body_nodes, time);
+        self.world = self.scene_graph.world_matrices();
+
+        // La cámara en órbita sigue al cuerpo seleccionado mientras se mueve
+        match self.selected {
+            Some(i) => {
+                let center = self.body_position(i);
+                self.controller.set_target(center, self.scene.bodies[i].radius);
+            }
+            None => self.controller.set_target(Vec3::zeros(), 0.0),
+        }
+        self.controller.update(dt, &mut self.camera);
+    }
+
+    // El primer cuerpo `sun` de la escena, en su posición del último `update`
+    pub fn light(&self) -> Light {
+        let sun = self
+            .scene
+            .bodies
+            .iter()
+            .position(|body| body.shader == ShaderKind::Sun);
+        let (source, radius) = match sun {
+            Some(i) => (
+                LightSource::Point(self.body_position(i)),
+                self.scene.bodies[i].radius,
+            ),
+            None => (Light::fallback().source, 0.0),
+        };
+        Light {
+            source,
+            intensity: self.scene.light.intensity,
+            inverse_square: self.scene.light.inverse_square,
+            radius,
+        }
+    }
+
+    // Los cuerpos que no emiten luz, como esferas que pueden tapar el sol. Si hay
+    // más de MAX_OCCLUDERS se quedan los primeros de la escena.
+    pub fn occluders(&self) -> Vec<Occluder> {
+        self.scene
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(|(_, body)| !matches!(body.shader, ShaderKind::Sun | ShaderKind::Star))
+            .take(MAX_OCCLUDERS)
+            .map(|(i, body)| Occluder {
+                center: self.body_position(i),
+                radius: body.radius,
+            })
+            .collect()
+    }
+
+    // Centro de mundo del cuerpo `i` en el último `update`
+    fn body_position(&self, i: usize) -> Vec3 {
+        let frame = self.world[self.body_nodes[i].frame];
+        Vec3::new(frame[(0, 3)], frame[(1, 3)], frame[(2, 3)])
+    }
+
+    pub fn view_proj(&self) -> Mat4 {
+        self.projection.matrix() * self.camera.view_matrix()
+    }
+
+    // Las estrellas están en el infinito: sin la traslación sólo las mueve la rotación
+    pub fn sky_view_proj(&self) -> Mat4 {
+        let mut view = self.camera.view_matrix();
+        view.set_column(3, &Vec4::w());
+        self.projection.matrix() * view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sun_at_origin(inverse_square: bool) -> Light {
+        Light {
+            source: LightSource::Point(Vec3::zeros()),
+            intensity: 2.0,
+            inverse_square,
+            radius: 0.0,
+        }
+    }
+
+    #[test]
+    fn point_light_points_at_the_sun() {
+        let (direction, irradiance) = sun_at_origin(false).at(&Vec3::new(0.0, 0.0, 3.0));
+        assert!((direction - Vec3::new(0.0, 0.0, -1.0)).norm() < 1e-6);
+        assert_eq!(irradiance, 2.0);
+    }
+
+    #[test]
+    fn inverse_square_falloff() {
+        let light = sun_at_origin(true);
+        let (_, near) = light.at(&Vec3::new(1.0, 0.0, 0.0));
+        let (_, far) = light.at(&Vec3::new(0.0, 2.0, 0.0));
+        assert!((near - 2.0).abs() < 1e-6);
+        assert!((far - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn eclipse_has_umbra_and_penumbra() {
+        // Sol de radio 1 a distancia 100 y una luna de radio 0.5 a 5 de la superficie
+        let light = Light {
+            source: LightSource::Point(Vec3::new(0.0, 0.0, 100.0)),
+            intensity: 1.0,
+            inverse_square: false,
+            radius: 1.0,
+        };
+        let moon = [Occluder { center: Vec3::new(0.0, 0.0, 5.0), radius: 0.5 }];
+
+        assert_eq!(light.visibility(&Vec3::zeros(), &moon), 0.0);
+        assert_eq!(light.visibility(&Vec3::new(3.0, 0.0, 0.0), &moon), 1.0);
+        let penumbra = light.visibility(&Vec3::new(0.5, 0.0, 0.0), &moon);
+        assert!(penumbra > 0.0 && penumbra < 1.0, "{penumbra}");
+        // Detrás del sol o dentro del oclusor no hay sombra
+        assert_eq!(light.visibility(&Vec3::new(0.0, 0.0, 5.2), &moon), 1.0);
+    }
+
+    #[test]
+    fn annular_eclipse_covers_the_area_ratio() {
+        assert!((covered_fraction(1.0, 0.5, 0.0) - 0.25).abs() < 1e-6);
+        assert_eq!(covered_fraction(0.0, 0.5, 0.4), 1.0);
+        assert_eq!(covered_fraction(0.0, 0.5, 0.6), 0.0);
+        // Dos discos iguales separados un radio: lente de área 2π/3 - √3/2
+        let lens = 2.0 * std::f32::consts::PI / 3.0 - 3.0_f32.sqrt() / 2.0;
+        let expected = lens / std::f32::consts::PI;
+        assert!((covered_fraction(1.0, 1.0, 1.0) - expected).abs() < 1e-5);
+    }
+}
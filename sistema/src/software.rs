@@ -0,0 +1,269 @@
+// software.rs - Renderer en CPU, alternativa a wgpu para máquinas sin GPU
+//
+// Sigue los mismos pasos que el pipeline de GPU: `vertex_shader` lleva los
+// vértices a pantalla, `triangle` rasteriza y genera fragmentos, y
+// `fragment_shader` los colorea antes de escribirlos en el framebuffer con
+// prueba de profundidad.
+
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::renderer::{Indices, create_sphere, mesh_from_obj, mesh_vertices};
+use crate::scene::{Rings, Scene};
+use crate::scene_graph::NodeId;
+use crate::shaders::{fragment_shader, ring_shader, vertex_shader};
+use crate::simulation::{Light, Occluder, Simulation};
+use crate::starfield::{self, Star};
+use crate::triangle::triangle;
+use crate::vertex::Vertex;
+use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use sistema::meshgen;
+
+pub struct Uniforms<'a> {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    // Cuadros transcurridos: los shaders de CPU escalan el tiempo por cuadro
+    pub time: u32,
+    pub light: Light,
+    // Anillos del cuerpo, para la sombra que proyectan sobre él
+    pub rings: Option<Rings>,
+    // Cuerpos que pueden eclipsar al sol
+    pub occluders: &'a [Occluder],
+}
+
+const FRAME_RATE: f32 = 60.0;
+// El color de fondo del pase de GPU (0.01, 0.01, 0.03 lineal) convertido a sRGB
+const BACKGROUND: u32 = 0x19_19_30;
+
+// NDC -> pixeles, con y hacia abajo como el framebuffer
+fn viewport_matrix(width: f32, height: f32) -> Mat4 {
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, width / 2.0,
+        0.0, -height / 2.0, 0.0, height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Malla indexada como lista de triángulos (tres vértices por cara)
+fn triangle_list(vertices: &[crate::renderer::Vertex], indices: &Indices) -> Vec<Vertex> {
+    indices
+        .iter()
+        .map(|i| {
+            let vertex = vertices[i as usize];
+            let [u, v] = vertex.uv;
+            Vertex::new(vertex.position.into(), vertex.normal.into(), Vec3::new(u, v, 0.0))
+        })
+        .collect()
+}
+
+// Mezcla `color` con opacidad `alpha` sobre un pixel 0xRRGGBB del framebuffer
+fn blend(dst: u32, color: Color, alpha: f32) -> u32 {
+    let [_, r, g, b] = dst.to_be_bytes();
+    Color::new(r, g, b).lerp(&color, alpha).to_hex()
+}
+
+// Tinte multiplicativo de la escena, como `color *= object.color` en shader.wgsl
+fn tint(color: Color, tint: [f32; 3]) -> Color {
+    Color::from_float(
+        color.r as f32 / 255.0 * tint[0],
+        color.g as f32 / 255.0 * tint[1],
+        color.b as f32 / 255.0 * tint[2],
+    )
+}
+
+pub struct SoftwareRenderer {
+    pub framebuffer: Framebuffer,
+    // Esfera unitaria como lista de triángulos (tres vértices por cara)
+    sphere: Vec<Vertex>,
+    // Malla propia de cada cuerpo que la declara, igual que la esfera
+    meshes: Vec<Option<Vec<Vertex>>>,
+    // Corona de cada cuerpo con anillos, con sus radios ya aplicados
+    rings: Vec<Option<Vec<Vertex>>>,
+    // Dirección y color ya en sRGB de cada estrella de fondo
+    stars: Vec<(Vec3, u32)>,
+    // Reutilizados entre cuerpos y cuadros
+    transformed: Vec<Vertex>,
+    fragments: Vec<Fragment>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32, scene: &Scene, stars: &[Star]) -> Self {
+        let (vertices, indices) = create_sphere();
+        let sphere = triangle_list(&vertices, &indices);
+        let meshes = scene
+            .bodies
+            .iter()
+            .map(|body| {
+                body.mesh.as_ref().map(|obj| {
+                    let (vertices, indices) = mesh_from_obj(obj);
+                    triangle_list(&vertices, &indices)
+                })
+            })
+            .collect();
+        let rings = scene
+            .bodies
+            .iter()
+            .map(|body| {
+                body.rings.map(|rings| {
+                    let (vertices, indices) = mesh_vertices(&meshgen::ring(rings.inner, rings.outer, 128));
+                    triangle_list(&vertices, &indices)
+                })
+            })
+            .collect();
+
+        let stars = stars
+            .iter()
+            .map(|star| {
+                let [r, g, b] = starfield::point_color(star).map(|c| c.powf(1.0 / 2.2));
+                (star.direction, Color::from_float(r, g, b).to_hex())
+            })
+            .collect();
+
+        let mut framebuffer = Framebuffer::new(width as usize, height as usize);
+        framebuffer.set_background_color(BACKGROUND);
+
+        SoftwareRenderer {
+            framebuffer,
+            sphere,
+            meshes,
+            rings,
+            stars,
+            transformed: Vec::new(),
+            fragments: Vec::new(),
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer = Framebuffer::new(width as usize, height as usize);
+        self.framebuffer.set_background_color(BACKGROUND);
+    }
+
+    pub fn render(&mut self, simulation: &Simulation) {
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let viewport = viewport_matrix(width as f32, height as f32);
+        self.framebuffer.clear();
+
+        // Estrellas como pixeles sueltos, detrás de cualquier cuerpo
+        let sky = viewport * simulation.sky_view_proj();
+        for (direction, color) in &self.stars {
+            let p = sky * Vec4::new(direction.x, direction.y, direction.z, 1.0);
+            if p.w <= 0.0 {
+                continue;
+            }
+            let (x, y) = (p.x / p.w, p.y / p.w);
+            if x >= 0.0 && y >= 0.0 {
+                self.framebuffer.set_current_color(*color);
+                self.framebuffer.point(x as usize, y as usize, 1.0);
+            }
+        }
+
+        let view_matrix = simulation.camera.view_matrix();
+        let projection_matrix = simulation.projection.matrix();
+        let light = simulation.light();
+        let occluders = simulation.occluders();
+        for (i, (body, nodes)) in simulation.scene.bodies.iter().zip(&simulation.body_nodes).enumerate() {
+            let uniforms = Uniforms {
+                model_matrix: simulation.world[nodes.mesh],
+                view_matrix,
+                projection_matrix,
+                viewport_matrix: viewport,
+                time: (simulation.time * FRAME_RATE) as u32,
+                light,
+                rings: body.rings,
+                occluders: &occluders,
+            };
+            // La esfera si el cuerpo no declara otra malla
+            let mesh = self.meshes[i].as_ref().unwrap_or(&self.sphere);
+            rasterize(mesh, &uniforms, &self.framebuffer, &mut self.transformed, &mut self.fragments);
+
+            for fragment in &self.fragments {
+                let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+                // Prueba de profundidad antes de sombrear: el fragment shader es lo caro
+                if fragment.depth >= self.framebuffer.zbuffer[y * width + x] {
+                    continue;
+                }
+                let color = fragment_shader(fragment, &uniforms, body.shader)
+                    .unwrap_or(Color::new(255, 255, 255));
+                let color = tint(color, body.color);
+                self.framebuffer.set_current_color(color.to_hex());
+                self.framebuffer.point(x, y, fragment.depth);
+            }
+        }
+
+        // Anillos semitransparentes después de los cuerpos opacos, del más lejano al
+        // más cercano; se mezclan sin escribir profundidad
+        let eye = simulation.camera.eye;
+        let mut ringed: Vec<_> = simulation
+            .scene
+            .bodies
+            .iter()
+            .zip(&simulation.body_nodes)
+            .enumerate()
+            .filter_map(|(i, (body, nodes))| Some((nodes.frame, nodes.rings?, body.rings?, i)))
+            .collect();
+        ringed.sort_by(|a, b| {
+            let distance = |frame: NodeId| {
+                let m = simulation.world[frame];
+                glm::distance2(&eye, &Vec3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]))
+            };
+            distance(b.0).total_cmp(&distance(a.0))
+        });
+
+        for (_, node, rings, body) in ringed {
+            let uniforms = Uniforms {
+                model_matrix: simulation.world[node],
+                view_matrix,
+                projection_matrix,
+                viewport_matrix: viewport,
+                time: (simulation.time * FRAME_RATE) as u32,
+                light,
+                rings: None,
+                occluders: &occluders,
+            };
+            let mesh = self.rings[body].as_deref().expect("malla de anillos creada en new");
+            rasterize(mesh, &uniforms, &self.framebuffer, &mut self.transformed, &mut self.fragments);
+
+            for fragment in &self.fragments {
+                let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+                let index = y * width + x;
+                if fragment.depth >= self.framebuffer.zbuffer[index] {
+                    continue;
+                }
+                let (color, alpha) = ring_shader(fragment, &uniforms, &rings);
+                if alpha <= 0.0 {
+                    continue;
+                }
+                let color = tint(color, rings.color);
+                self.framebuffer.buffer[index] = blend(self.framebuffer.buffer[index], color, alpha);
+            }
+        }
+    }
+}
+
+// Lista de triángulos a fragmentos en `fragments`; `transformed` guarda los
+// vértices ya en pantalla y se reutiliza entre llamadas
+fn rasterize(
+    mesh: &[Vertex],
+    uniforms: &Uniforms,
+    framebuffer: &Framebuffer,
+    transformed: &mut Vec<Vertex>,
+    fragments: &mut Vec<Fragment>,
+) {
+    transformed.clear();
+    transformed.extend(mesh.iter().map(|vertex| vertex_shader(vertex, uniforms)));
+
+    fragments.clear();
+    for face in transformed.chunks_exact(3) {
+        // Recorte simple: se descarta la cara si algún vértice queda fuera de [near, far]
+        if face
+            .iter()
+            .any(|v| !(0.0..=1.0).contains(&v.transformed_position.z))
+        {
+            continue;
+        }
+        triangle(&face[0], &face[1], &face[2], framebuffer.width, framebuffer.height, fragments);
+    }
+}
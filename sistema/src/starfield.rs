@@ -0,0 +1,406 @@
+// starfield.rs - Fondo de estrellas a partir de un catálogo real
+//
+// Cada estrella es un sprite (dos triángulos) expandido en el vertex shader; todas
+// se dibujan con una sola llamada instanciada. El catálogo es un CSV con columnas
+// ra (horas), dec (grados), vmag y bv; también se aceptan los nombres mag y ci de
+// la base HYG, así que se puede cargar un catálogo de decenas de miles de estrellas.
+
+use crate::renderer::wgsl_with_prelude;
+use nalgebra_glm::Vec3;
+use std::fmt;
+use std::path::Path;
+use wgpu::util::DeviceExt;
+
+pub const DEFAULT_CATALOG: &str = include_str!("../assets/bright_stars.csv");
+
+// Inclinación del ecuador respecto de la eclíptica (J2000)
+const OBLIQUITY: f32 = 23.439_f32 * std::f32::consts::PI / 180.0;
+// Índice B-V que se asume cuando el catálogo no lo trae (tipo solar)
+const DEFAULT_BV: f32 = 0.65;
+// Más brillante que esto no es una estrella de fondo (p. ej. el Sol en HYG)
+const BRIGHTEST_MAGNITUDE: f32 = -5.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Star {
+    pub direction: Vec3,
+    pub magnitude: f32,
+    pub bv: f32,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    Invalid {
+        origin: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io { path, source } => write!(f, "{}: {}", path, source),
+            CatalogError::Invalid {
+                origin,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", origin, line, message),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io { source, .. } => Some(source),
+            CatalogError::Invalid { .. } => None,
+        }
+    }
+}
+
+pub fn load_catalog(path: &Path) -> Result<Vec<Star>, CatalogError> {
+    let source = std::fs::read_to_string(path).map_err(|source| CatalogError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    parse_catalog(&source, &path.display().to_string())
+}
+
+// Las líneas vacías y las que empiezan con '#' se ignoran; la primera restante es
+// el encabezado. No se admiten campos entre comillas.
+pub fn parse_catalog(source: &str, origin: &str) -> Result<Vec<Star>, CatalogError> {
+    let invalid = |line: usize, message: String| CatalogError::Invalid {
+        origin: origin.to_string(),
+        line,
+        message,
+    };
+
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let Some((header_line, header)) = lines.next() else {
+        return Err(invalid(1, "el catálogo está vacío".to_string()));
+    };
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(c));
+    let required = |names: &[&str]| {
+        column(names).ok_or_else(|| invalid(header_line, format!("falta la columna '{}'", names[0])))
+    };
+    let ra = required(&["ra"])?;
+    let dec = required(&["dec"])?;
+    let vmag = required(&["vmag", "mag"])?;
+    let bv = column(&["bv", "ci"]);
+
+    let mut stars = Vec::new();
+    for (line, text) in lines {
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        if fields.len() != columns.len() {
+            return Err(invalid(
+                line,
+                format!("se esperaban {} campos, hay {}", columns.len(), fields.len()),
+            ));
+        }
+
+        // `parse` también acepta "nan" e "inf"
+        let number = |index: usize| {
+            let value = fields[index].parse::<f32>().ok().filter(|x| x.is_finite());
+            value.ok_or_else(|| {
+                invalid(line, format!("'{}' no es un número válido para '{}'", fields[index], columns[index]))
+            })
+        };
+        let ra = number(ra)?;
+        let dec = number(dec)?;
+        let magnitude = number(vmag)?;
+        let bv = match bv {
+            Some(index) if !fields[index].is_empty() => number(index)?,
+            _ => DEFAULT_BV,
+        };
+
+        if !(0.0..24.0).contains(&ra) {
+            return Err(invalid(line, format!("ra debe estar en [0, 24) horas, es {}", ra)));
+        }
+        if !(-90.0..=90.0).contains(&dec) {
+            return Err(invalid(line, format!("dec debe estar en [-90, 90] grados, es {}", dec)));
+        }
+        if magnitude < BRIGHTEST_MAGNITUDE {
+            continue;
+        }
+
+        stars.push(Star {
+            direction: equatorial_to_world(ra, dec),
+            magnitude,
+            bv,
+        });
+    }
+
+    Ok(stars)
+}
+
+// Ascensión recta (horas) y declinación (grados) -> dirección en el mundo
+pub fn equatorial_to_world(ra_hours: f32, dec_degrees: f32) -> Vec3 {
+    let ra = ra_hours * std::f32::consts::TAU / 24.0;
+    let dec = dec_degrees.to_radians();
+    let (x, y, z) = (dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin());
+
+    // Ecuatorial -> eclíptica (rotación sobre x)
+    let (sin_e, cos_e) = OBLIQUITY.sin_cos();
+    let (ey, ez) = (y * cos_e + z * sin_e, -y * sin_e + z * cos_e);
+
+    // Eclíptica con z al norte -> mundo con y hacia arriba, igual que orbit.rs
+    Vec3::new(x, ez, -ey)
+}
+
+// Temperatura efectiva a partir del índice B-V (Ballesteros, 2012)
+pub fn bv_to_temperature(bv: f32) -> f32 {
+    let bv = bv.clamp(-0.4, 2.0);
+    4600.0 * (1.0 / (0.92 * bv + 1.7) + 1.0 / (0.92 * bv + 0.62))
+}
+
+// Color de un cuerpo negro a esa temperatura (aproximación de Tanner Helland),
+// convertido a lineal porque la superficie es sRGB
+pub fn bv_to_color(bv: f32) -> [f32; 3] {
+    let t = bv_to_temperature(bv) / 100.0;
+
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    [red, green, blue].map(|c| (c.clamp(0.0, 255.0) / 255.0).powf(2.2))
+}
+
+// Flujo relativo a una estrella de magnitud 0 (escala de Pogson)
+pub fn relative_flux(magnitude: f32) -> f32 {
+    10f32.powf(-0.4 * magnitude)
+}
+
+// Intensidad en pantalla: raíz del flujo para que las estrellas débiles no
+// desaparezcan; lo que excede 1 hace crecer el sprite en lugar de saturar
+fn sprite(magnitude: f32) -> (f32, f32) {
+    let intensity = relative_flux(magnitude).sqrt();
+    let brightness = intensity.min(1.0);
+    let size = 1.5 + 1.5 * intensity.min(2.0);
+    (size, brightness)
+}
+
+// Color lineal ya multiplicado por el brillo, para dibujar la estrella como un punto
+pub fn point_color(star: &Star) -> [f32; 3] {
+    let (_, brightness) = sprite(star.magnitude);
+    bv_to_color(star.bv).map(|c| c * brightness)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct StarInstance {
+    direction: [f32; 3],
+    size: f32, // Radio del sprite en pixeles
+    color: [f32; 3],
+    brightness: f32,
+}
+
+impl StarInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32, 2 => Float32x3, 3 => Float32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<StarInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+pub struct Starfield {
+    pipeline: wgpu::RenderPipeline,
+    instance_buffer: wgpu::Buffer,
+    count: u32,
+}
+
+impl Starfield {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        uniform_layout: &wgpu::BindGroupLayout,
+        stars: &[Star],
+    ) -> Self {
+        let instances: Vec<StarInstance> = stars
+            .iter()
+            .map(|star| {
+                let (size, brightness) = sprite(star.magnitude);
+                StarInstance {
+                    direction: star.direction.into(),
+                    size,
+                    color: bv_to_color(star.bv),
+                    brightness,
+                }
+            })
+            .collect();
+
+        // Un buffer vacío no es válido; con cero estrellas no se dibuja nada
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Star Instance Buffer"),
+            contents: if instances.is_empty() {
+                &[0; std::mem::size_of::<StarInstance>()]
+            } else {
+                bytemuck::cast_slice(&instances)
+            },
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Starfield Shader"),
+            source: wgpu::ShaderSource::Wgsl(wgsl_with_prelude(include_str!("starfield.wgsl")).into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Starfield Pipeline Layout"),
+            bind_group_layouts: &[uniform_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Suma de luz sin escribir profundidad: los cuerpos se dibujan después y las tapan
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Starfield Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[StarInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }),
+                Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::empty(),
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Starfield {
+            pipeline,
+            instance_buffer,
+            count: instances.len() as u32,
+        }
+    }
+
+    // El bind group 0 (uniforms globales) debe estar asignado
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..6, 0..self.count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Columnas con los nombres de HYG (mag, ci) entre otras que se ignoran
+    const HYG: &str = "\
+# extracto de hygdata
+id,proper,ra,dec,mag,ci
+
+0,Sol,0.0,0.0,-26.7,0.656
+32263,Sirius,6.752481,-16.716116,-1.44,0.009
+24378,Betelgeuse,5.919529,7.407063,0.45,1.500
+1,,0.0,0.0,6.0,
+";
+
+    #[test]
+    fn catalogs_accept_hyg_columns() {
+        let stars = parse_catalog(HYG, "hyg.csv").unwrap();
+        // El Sol queda fuera por ser más brillante que BRIGHTEST_MAGNITUDE
+        assert_eq!(stars.len(), 3);
+        assert_eq!(stars[0].magnitude, -1.44);
+        assert_eq!(stars[1].bv, 1.5);
+        // bv vacío toma el valor por defecto; ra = dec = 0 es el equinoccio, +x
+        assert_eq!(stars[2].bv, DEFAULT_BV);
+        assert!((stars[2].direction - Vec3::x()).norm() < 1e-6);
+    }
+
+    #[test]
+    fn catalog_errors_report_the_line() {
+        let error = |source: &str| parse_catalog(source, "c.csv").unwrap_err().to_string();
+
+        assert_eq!(error("ra,dec,vmag\n1.0,2.0,3.0\n1.0,abc,3.0\n"), "c.csv:3: 'abc' no es un número válido para 'dec'");
+        assert_eq!(error("ra,dec,vmag\n1.0,2.0,NaN\n"), "c.csv:2: 'NaN' no es un número válido para 'vmag'");
+        assert_eq!(error("ra,dec,vmag,bv\n1.0,2.0,3.0,inf\n"), "c.csv:2: 'inf' no es un número válido para 'bv'");
+        assert_eq!(error("ra,dec,vmag\n\n1.0,2.0\n"), "c.csv:3: se esperaban 3 campos, hay 2");
+        assert_eq!(error("# sin datos\nra,dec,vmag\n25.0,0.0,1.0\n"), "c.csv:3: ra debe estar en [0, 24) horas, es 25");
+        assert_eq!(error("ra,vmag\n1.0,2.0\n"), "c.csv:1: falta la columna 'dec'");
+        assert_eq!(error("# nada\n\n"), "c.csv:1: el catálogo está vacío");
+    }
+
+    #[test]
+    fn color_goes_from_blue_to_red() {
+        // Estrellas O/B (bv < 0): más azul que rojo; M (bv ~ 2, unos 3000 K): poco azul
+        let [r, g, b] = bv_to_color(-0.4);
+        assert!(b >= r && b > g && b == 1.0, "{:?}", [r, g, b]);
+        let [r, g, b] = bv_to_color(2.0);
+        assert!(r == 1.0 && r > g && g > b && b < 0.25, "{:?}", [r, g, b]);
+        // Fuera del rango de la fórmula se satura en los extremos
+        assert_eq!(bv_to_color(5.0), bv_to_color(2.0));
+    }
+
+    #[test]
+    fn five_magnitudes_are_a_factor_of_100() {
+        for magnitude in [-1.5, 0.0, 2.5, 6.0] {
+            let ratio = relative_flux(magnitude) / relative_flux(magnitude + 5.0);
+            assert!((ratio - 100.0).abs() < 1e-3, "{}: {}", magnitude, ratio);
+        }
+        assert_eq!(relative_flux(0.0), 1.0);
+    }
+}
@@ -0,0 +1,71 @@
+// starfield.wgsl - Estrellas del catálogo como sprites instanciados
+
+// `struct Uniforms` la genera renderer::uniforms_wgsl y se agrega al final del código
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct StarInput {
+    @location(0) direction: vec3<f32>,
+    @location(1) size: f32,
+    @location(2) color: vec3<f32>,
+    @location(3) brightness: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, star: StarInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Dos triángulos por estrella, con las esquinas en [-1, 1]
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // Las estrellas están en el infinito: sólo importa la rotación de la cámara.
+    // El sprite se expande en pixeles, así que su tamaño no depende de la distancia.
+    let center = uniforms.sky_view_proj * vec4<f32>(star.direction, 1.0);
+    let offset = corner * star.size * 2.0 / uniforms.resolution * center.w;
+    output.clip_position = vec4<f32>(center.xy + offset, center.zw);
+    output.corner = corner;
+
+    // Centelleo leve con una fase distinta por estrella
+    let phase = fract(sin(dot(star.direction, vec3<f32>(12.9898, 78.233, 37.719))) * 43758.5453);
+    let twinkle = 0.9 + 0.1 * sin(uniforms.time * 3.0 + phase * 6.2831);
+    output.color = star.color * star.brightness * twinkle;
+
+    return output;
+}
+
+// La pasada de escena tiene dos destinos (color y emisión, ver post.rs); el
+// fondo no alimenta el bloom y su pipeline no escribe el segundo
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) emission: vec4<f32>,
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> FragmentOutput {
+    let r2 = dot(input.corner, input.corner);
+    if (r2 > 1.0) {
+        discard;
+    }
+
+    // Perfil gaussiano: núcleo brillante y borde suave
+    let falloff = exp(-r2 * 4.0);
+    var output: FragmentOutput;
+    output.color = vec4<f32>(input.color * falloff, 1.0);
+    output.emission = vec4<f32>(0.0);
+    return output;
+}
@@ -0,0 +1,76 @@
+// triangle.rs - Rasterización de triángulos con coordenadas baricéntricas
+//
+// Recibe vértices ya transformados a pantalla por `vertex_shader` (x, y en
+// pixeles con y hacia abajo, z en [0, 1]) y genera un `Fragment` por cada pixel
+// cuyo centro cae dentro del triángulo.
+
+use crate::fragment::Fragment;
+use crate::vertex::Vertex;
+use nalgebra_glm::{Vec2, Vec3};
+
+// Doble del área con signo de (a, b, p); negativa si p queda a la izquierda de a->b en pantalla
+fn edge_function(a: &Vec2, b: &Vec2, p: &Vec2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// Agrega a `fragments` los fragmentos del triángulo dentro de width x height.
+// Los triángulos antihorarios vistos desde la cámara son la cara frontal, como en
+// el pipeline de GPU; al invertir y en pantalla su área queda negativa y el resto
+// se descarta.
+pub fn triangle(
+    v1: &Vertex,
+    v2: &Vertex,
+    v3: &Vertex,
+    width: usize,
+    height: usize,
+    fragments: &mut Vec<Fragment>,
+) {
+    let (a, b, c) = (
+        v1.transformed_position,
+        v2.transformed_position,
+        v3.transformed_position,
+    );
+    let (a2, b2, c2) = (a.xy(), b.xy(), c.xy());
+
+    let area = edge_function(&a2, &b2, &c2);
+    if area >= 0.0 {
+        return;
+    }
+
+    // Caja contenedora recortada al framebuffer
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as isize).min(width as isize - 1);
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as isize).min(height as isize - 1);
+    if max_x < 0 || max_y < 0 {
+        return;
+    }
+
+    for y in min_y..=max_y as usize {
+        for x in min_x..=max_x as usize {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+            // Coordenadas baricéntricas: peso de cada vértice en el pixel
+            let w1 = edge_function(&b2, &c2, &p) / area;
+            let w2 = edge_function(&c2, &a2, &p) / area;
+            let w3 = edge_function(&a2, &b2, &p) / area;
+            if w1 < 0.0 || w2 < 0.0 || w3 < 0.0 {
+                continue;
+            }
+
+            let depth = a.z * w1 + b.z * w2 + c.z * w3;
+            let normal = (v1.transformed_normal * w1
+                + v2.transformed_normal * w2
+                + v3.transformed_normal * w3)
+                .normalize();
+            let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+
+            fragments.push(Fragment::new(
+                Vec3::new(x as f32, y as f32, depth),
+                normal,
+                depth,
+                vertex_position,
+            ));
+        }
+    }
+}
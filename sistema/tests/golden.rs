@@ -0,0 +1,228 @@
+// golden.rs - Imágenes de referencia para cada shader de planeta
+//
+// Cada caso dibuja un solo cuerpo con `sistema --headless` (el adaptador de
+// software de wgpu basta, no hace falta GPU) en tiempos fijos y compara el
+// resultado con tests/golden/<shader>_t<tiempo>.png. La comparación es
+// perceptual: diferencia de color ΔE en CIELAB por pixel, tolerando unos pocos
+// pixeles distintos en los bordes. Si falla se escriben la imagen obtenida y un
+// mapa de diferencias junto a la ruta que indica el mensaje.
+//
+// Para regenerar las referencias:
+//     UPDATE_GOLDEN=1 cargo test --test golden
+
+use sistema::cielab::delta_e;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SIZE: u32 = 64;
+const TIMES: [f32; 2] = [0.0, 7.5];
+
+// ΔE a partir del cual un pixel cuenta como distinto (~2.3 es apenas perceptible)
+const MAX_DELTA_E: f32 = 6.0;
+// Fracción de pixeles distintos que se tolera (bordes rasterizados distinto)
+const MAX_DIFFERENT: f32 = 0.01;
+
+struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+fn read_png(path: &Path) -> Image {
+    let file = File::open(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+    let mut reader = decoder.read_info().unwrap();
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba, "{}", path.display());
+    rgba.truncate(info.buffer_size());
+    Image {
+        width: info.width,
+        height: info.height,
+        rgba,
+    }
+}
+
+fn write_png(path: &Path, image: &Image) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(&image.rgba).unwrap();
+}
+
+// Devuelve la fracción de pixeles distintos y un mapa con ellos en rojo sobre la
+// referencia atenuada
+fn compare(expected: &Image, actual: &Image) -> (f32, Image) {
+    let mut different = 0;
+    let mut diff = Vec::with_capacity(expected.rgba.len());
+
+    for (e, a) in expected.rgba.chunks(4).zip(actual.rgba.chunks(4)) {
+        let delta = delta_e(e, a);
+        if delta > MAX_DELTA_E {
+            different += 1;
+            let intensity = (128.0 + delta * 4.0).min(255.0) as u8;
+            diff.extend_from_slice(&[intensity, 0, 0, 255]);
+        } else {
+            let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 / 3) as u8;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    let fraction = different as f32 / (expected.width * expected.height) as f32;
+    let diff = Image {
+        width: expected.width,
+        height: expected.height,
+        rgba: diff,
+    };
+    (fraction, diff)
+}
+
+// Cada caso es un shader de cuerpo, salvo `atmosphere`: el planeta rocoso con
+// la capa de atmósfera por defecto, y `mesh`: la luna con la malla de
+// assets/asteroide.obj
+fn scene(case: &str) -> String {
+    let asteroid = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join("asteroide.obj");
+    // El planeta con anillos necesita más distancia para que entren en el cuadro
+    let (shader, extra, distance) = match case {
+        "ringed" => ("ringed", "rings = { inner = 1.3, outer = 2.3 }\n".to_string(), 6.0),
+        "atmosphere" => ("rocky", "atmosphere = {}\n".to_string(), 3.2),
+        "mesh" => ("moon", format!("mesh = {:?}\n", asteroid.display().to_string()), 3.2),
+        _ => (case, String::new(), 3.2),
+    };
+    format!(
+        "[camera]\ndistance = {}\npitch = 20.0\n\n\
+         [[body]]\nname = \"cuerpo\"\nshader = \"{}\"\nradius = 1.0\n\
+         tilt = 15.0\nrotation_period = 20.0\n{}",
+        distance, shader, extra
+    )
+}
+
+fn render(shader: &str, time: f32) -> (PathBuf, Image) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("golden")
+        .join(format!("{}_t{}", shader, time));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let scene_path = dir.join("scene.toml");
+    std::fs::write(&scene_path, scene(shader)).unwrap();
+    // Sin estrellas de fondo: las referencias sólo dependen del shader del cuerpo
+    let stars_path = dir.join("stars.csv");
+    std::fs::write(&stars_path, "ra,dec,vmag,bv\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sistema"))
+        .arg("--headless")
+        .args(["--scene".as_ref(), scene_path.as_os_str()])
+        .args(["--stars".as_ref(), stars_path.as_os_str()])
+        .args(["--out".as_ref(), dir.as_os_str()])
+        .args(["--size", &format!("{}x{}", SIZE, SIZE)])
+        .args(["--time", &time.to_string()])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "sistema --headless falló:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let image = read_png(&dir.join("frame_0000.png"));
+    (dir, image)
+}
+
+fn check(shader: &str) {
+    let references = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+
+    for time in TIMES {
+        let name = format!("{}_t{}", shader, time);
+        let reference = references.join(format!("{}.png", name));
+        let (dir, actual) = render(shader, time);
+
+        if update {
+            std::fs::create_dir_all(&references).unwrap();
+            write_png(&reference, &actual);
+            continue;
+        }
+        if !reference.exists() {
+            failures.push(format!(
+                "{}: no existe la referencia {} (UPDATE_GOLDEN=1 para crearla)",
+                name,
+                reference.display()
+            ));
+            continue;
+        }
+
+        let expected = read_png(&reference);
+        assert_eq!(
+            (expected.width, expected.height),
+            (actual.width, actual.height),
+            "{}: tamaño distinto al de la referencia",
+            name
+        );
+
+        let (fraction, diff) = compare(&expected, &actual);
+        if fraction > MAX_DIFFERENT {
+            write_png(&dir.join("actual.png"), &actual);
+            write_png(&dir.join("diff.png"), &diff);
+            failures.push(format!(
+                "{}: {:.1}% de pixeles con ΔE > {} (máximo {:.1}%); ver {}",
+                name,
+                fraction * 100.0,
+                MAX_DELTA_E,
+                MAX_DIFFERENT * 100.0,
+                dir.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn sun() {
+    check("sun");
+}
+
+#[test]
+fn rocky() {
+    check("rocky");
+}
+
+#[test]
+fn gas_giant() {
+    check("gas_giant");
+}
+
+#[test]
+fn ringed() {
+    check("ringed");
+}
+
+#[test]
+fn volcanic() {
+    check("volcanic");
+}
+
+#[test]
+fn moon() {
+    check("moon");
+}
+
+#[test]
+fn star() {
+    check("star");
+}
+
+#[test]
+fn atmosphere() {
+    check("atmosphere");
+}
+
+#[test]
+fn mesh() {
+    check("mesh");
+}